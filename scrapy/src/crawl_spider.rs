@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+
+use crate::{LinkExtractor, Request, Response, SelectorError, Spider};
+
+/// The metadata entry recording which rule a request was extracted by.
+const RULE_META: &str = "crawl_rule";
+
+/// The callback of requests extracted by a rule without a callback, which are
+/// only followed.
+const FOLLOW_CALLBACK: &str = "crawl_spider_follow";
+
+/// How a [`CrawlSpider`] handles the links matched by a [`LinkExtractor`].
+#[derive(Debug, Clone)]
+pub struct Rule {
+    links: LinkExtractor,
+    callback: Option<String>,
+    follow: Option<bool>,
+}
+
+impl Rule {
+    /// Creates a rule following the links `links` extracts.
+    pub fn new(links: LinkExtractor) -> Self {
+        Self {
+            links,
+            callback: None,
+            follow: None,
+        }
+    }
+
+    /// Sets the spider callback the matched pages are scraped with, see
+    /// [`Spider::parse`].
+    pub fn callback<C>(mut self, callback: C) -> Self
+    where
+        C: Into<String>,
+    {
+        self.callback = Some(callback.into());
+        self
+    }
+
+    /// Sets whether the rules are applied to the matched pages in turn. By
+    /// default, pages are followed only if the rule has no callback.
+    pub fn follow(mut self, follow: bool) -> Self {
+        self.follow = Some(follow);
+        self
+    }
+
+    fn follows(&self) -> bool {
+        self.follow.unwrap_or(self.callback.is_none())
+    }
+}
+
+/// A [`Spider`] crawling a site by rules, rather than by returning requests
+/// from `scrape`.
+///
+/// The start pages, and the pages matched by rules that follow, have their
+/// links extracted by every rule. A link matched by several rules is handled
+/// by the first one. Pages matched by a rule with a callback are scraped by
+/// the wrapped spider's [`parse`](Spider::parse) with that callback, while the
+/// start pages are scraped by its [`scrape`](Spider::scrape). Items and
+/// requests returned by the wrapped spider are passed on as they are.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::{CrawlSpider, LinkExtractor, Rule};
+/// # use async_trait::async_trait;
+/// # use scrapy::{Request, Response, Spider};
+/// #
+/// # struct BooksSpider;
+/// #
+/// # #[async_trait]
+/// # impl Spider for BooksSpider {
+/// #     type Item = String;
+/// #     type Error = scrapy::Error;
+/// #
+/// #     fn name(&self) -> String {
+/// #         String::from("books")
+/// #     }
+/// #
+/// #     async fn scrape(&self, _: &Response) -> Result<(Vec<String>, Vec<Request>), scrapy::Error> {
+/// #         Ok((Vec::new(), Vec::new()))
+/// #     }
+/// # }
+///
+/// let spider = CrawlSpider::new(BooksSpider)
+///     .rule(Rule::new(LinkExtractor::new().restrict_css(".side_categories")))
+///     .rule(Rule::new(LinkExtractor::new().restrict_css("li.next")))
+///     .rule(Rule::new(LinkExtractor::new().restrict_css(".product_pod h3")).callback("parse_product"));
+/// ```
+pub struct CrawlSpider<S> {
+    spider: S,
+    rules: Vec<Rule>,
+}
+
+impl<S> CrawlSpider<S> {
+    /// Creates a crawl spider without rules, scraping pages with `spider`.
+    pub fn new(spider: S) -> Self {
+        Self {
+            spider,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Appends a rule. Rules are tried in the order they are added.
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The wrapped spider.
+    pub fn spider(&self) -> &S {
+        &self.spider
+    }
+
+    /// Extracts the links of a page matched by the rules, each tagged with the
+    /// rule that matched it.
+    fn follow(&self, response: &Response) -> Result<Vec<Request>, SelectorError> {
+        let document = response.document();
+
+        let mut seen = HashSet::new();
+        let mut requests = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            for request in rule.links.extract_from(&document, &response.url)? {
+                if seen.insert(request.url.clone()) {
+                    let callback = rule.callback.as_deref().unwrap_or(FOLLOW_CALLBACK);
+                    requests.push(request.callback(callback).meta(RULE_META, index));
+                }
+            }
+        }
+
+        Ok(requests)
+    }
+}
+
+#[async_trait]
+impl<S> Spider for CrawlSpider<S>
+where
+    S: Spider,
+    S::Item: Send,
+    S::Error: From<SelectorError> + Send,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn name(&self) -> String {
+        self.spider.name()
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        self.spider.start_urls()
+    }
+
+    fn start_requests(&self) -> Vec<Request> {
+        self.spider.start_requests()
+    }
+
+    async fn scrape(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<Self::Item>, Vec<Request>), Self::Error> {
+        let (items, mut requests) = self.spider.scrape(response).await?;
+        requests.extend(self.follow(response)?);
+        Ok((items, requests))
+    }
+
+    async fn parse(
+        &self,
+        callback: &str,
+        response: &Response,
+    ) -> Option<Result<(Vec<Self::Item>, Vec<Request>), Self::Error>> {
+        let rule = response
+            .request
+            .meta
+            .get(RULE_META)
+            .and_then(|index| index.as_u64())
+            .and_then(|index| self.rules.get(index as usize));
+
+        // Requests the wrapped spider made itself go to its own callbacks.
+        let Some(rule) = rule else {
+            return self.spider.parse(callback, response).await;
+        };
+
+        let (items, mut requests) = match &rule.callback {
+            Some(callback) => match self.spider.parse(callback, response).await? {
+                Ok(scraped) => scraped,
+                Err(err) => return Some(Err(err)),
+            },
+            None => (Vec::new(), Vec::new()),
+        };
+
+        if rule.follows() {
+            match self.follow(response) {
+                Ok(links) => requests.extend(links),
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+
+        Some(Ok((items, requests)))
+    }
+
+    fn is_retryable(&self, error: &Self::Error) -> bool {
+        self.spider.is_retryable(error)
+    }
+}
@@ -1,23 +1,60 @@
-use std::time::Duration;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
-use crate::Crawler;
+use tokio::sync::Barrier;
+
+use crate::{
+    CrawlHandle, Crawler, DownloadSlot, Downloader, DownloaderMiddleware, DupeFilter,
+    FingerprintDupeFilter, HttpDownloader, ItemPipeline, PriorityScheduler, RetryPolicy, Scheduler,
+    SlotKey, StatsCollector,
+};
+
+use super::{
+    limits::CrawlLimits, middleware::MiddlewareDownloader, pipelines::ErasedPipeline,
+    slots::Politeness, DupeFilterFactory, SchedulerFactory,
+};
 
 pub struct CrawlerBuilder {
-    delay: Duration,
     crawling_concurrency: usize,
     processing_concurrency: usize,
     crawling_queue_capacity: Option<usize>,
     processing_queue_capacity: Option<usize>,
+    shutdown_timeout: Duration,
+    job_dir: Option<PathBuf>,
+    persist_interval: Duration,
+    downloader: Option<Arc<dyn Downloader>>,
+    downloader_middlewares: Vec<Arc<dyn DownloaderMiddleware>>,
+    dupe_filter: DupeFilterFactory,
+    item_pipelines: Vec<ErasedPipeline>,
+    limits: CrawlLimits,
+    politeness: Politeness,
+    retry: Option<RetryPolicy>,
+    robots_user_agent: Option<String>,
+    scheduler: SchedulerFactory,
+    stats: StatsCollector,
+    stats_interval: Option<Duration>,
 }
 
 impl Default for CrawlerBuilder {
     fn default() -> Self {
         Self {
-            delay: Duration::from_millis(250),
             crawling_concurrency: 2,
             processing_concurrency: 500,
             crawling_queue_capacity: None,
             processing_queue_capacity: None,
+            shutdown_timeout: Duration::from_secs(30),
+            job_dir: None,
+            persist_interval: Duration::from_secs(30),
+            downloader: None,
+            downloader_middlewares: Vec::new(),
+            dupe_filter: Arc::new(|| Box::<FingerprintDupeFilter>::default()),
+            item_pipelines: Vec::new(),
+            limits: CrawlLimits::default(),
+            politeness: Politeness::default(),
+            retry: None,
+            robots_user_agent: None,
+            scheduler: Arc::new(|| Box::<PriorityScheduler>::default()),
+            stats: StatsCollector::new(),
+            stats_interval: Some(Duration::from_secs(60)),
         }
     }
 }
@@ -27,16 +64,53 @@ impl CrawlerBuilder {
         Self::default()
     }
 
+    /// Sets the minimum time between two scrapes starting on the same host.
     pub fn delay(mut self, delay: Duration) -> Self {
-        self.delay = delay;
+        self.politeness.default = self.politeness.default.delay(delay);
         self
     }
 
+    /// Adds a random extra delay of up to `jitter` between scrapes on the
+    /// same host.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.politeness.default = self.politeness.default.jitter(jitter);
+        self
+    }
+
+    /// Sets how many URLs may be scraped at the same time across all hosts.
     pub fn crawling_concurrency(mut self, crawling_concurrency: usize) -> Self {
         self.crawling_concurrency = crawling_concurrency;
         self
     }
 
+    /// Sets how many URLs may be scraped at the same time on the same host.
+    pub fn slot_concurrency(mut self, slot_concurrency: usize) -> Self {
+        self.politeness.default = self.politeness.default.concurrency(slot_concurrency);
+        self
+    }
+
+    /// Sets how URLs are grouped into download slots. Each slot has its own
+    /// concurrency and delay, so a slow site doesn't hold back the others.
+    pub fn slot_key(mut self, slot_key: SlotKey) -> Self {
+        self.politeness.key = slot_key;
+        self
+    }
+
+    /// Overrides the slot settings for `domain` and its subdomains.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - The domain, such as `example.com`.
+    /// * `slot` - The settings replacing the crawler-wide delay, jitter and
+    ///   slot concurrency for that domain.
+    pub fn domain_slot<D>(mut self, domain: D, slot: DownloadSlot) -> Self
+    where
+        D: Into<String>,
+    {
+        self.politeness.domains.insert(domain.into(), slot);
+        self
+    }
+
     pub fn processing_concurrency(mut self, processing_concurrency: usize) -> Self {
         self.processing_concurrency = processing_concurrency;
         self
@@ -58,15 +132,217 @@ impl CrawlerBuilder {
         self
     }
 
+    /// Sets how long in-flight scrapes may keep running after a stop is requested.
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Persists the crawl state to `job_dir`, so that running again with the
+    /// same directory resumes the crawl instead of starting over. A crawl
+    /// fails if the directory cannot be opened, and closes with
+    /// [`CloseReason::JobDirFailed`](crate::CloseReason::JobDirFailed) if
+    /// writing to it fails.
+    pub fn job_dir<P>(mut self, job_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.job_dir = Some(job_dir.into());
+        self
+    }
+
+    /// Sets how often the job directory's journal is compacted.
+    pub fn persist_interval(mut self, persist_interval: Duration) -> Self {
+        self.persist_interval = persist_interval;
+        self
+    }
+
+    /// Sets the filter deciding which URLs are duplicates. Each crawl starts
+    /// from a fresh clone of `dupe_filter`.
+    pub fn dupe_filter<F>(mut self, dupe_filter: F) -> Self
+    where
+        F: DupeFilter + Clone + Sync + 'static,
+    {
+        self.dupe_filter = Arc::new(move || Box::new(dupe_filter.clone()));
+        self
+    }
+
+    /// Sets how many links away from the start URLs the crawl may go. Start
+    /// URLs have depth 0, and deeper links are dropped without being queued.
+    pub fn max_depth<O>(mut self, max_depth: O) -> Self
+    where
+        O: Into<Option<usize>>,
+    {
+        self.limits.max_depth = max_depth.into();
+        self
+    }
+
+    /// Closes the crawl with [`CloseReason::MaxPages`](crate::CloseReason::MaxPages)
+    /// once `max_pages` URLs have been scraped. Retries of a URL do not count
+    /// again, and run to completion before the crawl closes.
+    pub fn max_pages<O>(mut self, max_pages: O) -> Self
+    where
+        O: Into<Option<usize>>,
+    {
+        self.limits.max_pages = max_pages.into();
+        self
+    }
+
+    /// Closes the crawl with [`CloseReason::MaxItems`](crate::CloseReason::MaxItems)
+    /// once `max_items` items have been processed.
+    pub fn max_items<O>(mut self, max_items: O) -> Self
+    where
+        O: Into<Option<usize>>,
+    {
+        self.limits.max_items = max_items.into();
+        self
+    }
+
+    /// Closes the crawl with [`CloseReason::MaxErrors`](crate::CloseReason::MaxErrors)
+    /// once `max_errors` scrape or process errors have occurred.
+    pub fn max_errors<O>(mut self, max_errors: O) -> Self
+    where
+        O: Into<Option<usize>>,
+    {
+        self.limits.max_errors = max_errors.into();
+        self
+    }
+
+    /// Closes the crawl with [`CloseReason::Timeout`](crate::CloseReason::Timeout)
+    /// once it has run for `timeout`, including time spent paused.
+    pub fn timeout<O>(mut self, timeout: O) -> Self
+    where
+        O: Into<Option<Duration>>,
+    {
+        self.limits.timeout = timeout.into();
+        self
+    }
+
+    /// Sets the downloader fetching every request of the crawl, an
+    /// [`HttpDownloader`] by default.
+    pub fn downloader<D>(mut self, downloader: D) -> Self
+    where
+        D: Downloader + 'static,
+    {
+        self.downloader = Some(Arc::new(downloader));
+        self
+    }
+
+    /// Adds a middleware around every download. Requests pass through the
+    /// middlewares in the order they were added, and responses in reverse
+    /// order.
+    pub fn downloader_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: DownloaderMiddleware + 'static,
+    {
+        self.downloader_middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Adds a stage to the item pipeline. Items go through the stages in the
+    /// order they were added.
+    ///
+    /// Pipelines must accept the items of the spiders crawled: crawling a
+    /// spider with another item type fails with
+    /// [`Error::PipelineItemType`](crate::Error::PipelineItemType) before
+    /// anything is crawled.
+    pub fn item_pipeline<T, P>(mut self, pipeline: P) -> Self
+    where
+        T: 'static,
+        P: ItemPipeline<T> + 'static,
+    {
+        self.item_pipelines.push(ErasedPipeline::new(pipeline));
+        self
+    }
+
+    /// Sets the scheduler deciding which queued URL is scraped next. Each
+    /// crawl starts from a fresh clone of `scheduler`.
+    pub fn scheduler<S>(mut self, scheduler: S) -> Self
+    where
+        S: Scheduler + Clone + Sync + 'static,
+    {
+        self.scheduler = Arc::new(move || Box::new(scheduler.clone()));
+        self
+    }
+
+    /// Retries failed scrapes according to `retry`. Without a retry policy,
+    /// a failed URL is given up on right away.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Makes the crawler obey robots.txt. Each site's `/robots.txt` is
+    /// fetched once per crawl, through the crawler's downloader and
+    /// middlewares like any other request. Disallowed URLs are dropped before
+    /// they are queued, and its `Crawl-delay` raises the site's delay.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - The crawler's user agent, such as `mybot/1.0`, sent
+    ///   when fetching robots.txt. Groups are matched against its product
+    ///   token, the part before the `/`.
+    pub fn obey_robots_txt<U>(mut self, user_agent: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.robots_user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the collector the crawls record their stats in, so spiders and
+    /// pipelines given a clone of it can record theirs in the same place. The
+    /// collector is never cleared, so its stats add up across crawls.
+    pub fn stats(mut self, stats: StatsCollector) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Sets how often the stats are logged while crawling, every minute by
+    /// default. They are always logged when the crawl finishes.
+    pub fn stats_interval<O>(mut self, stats_interval: O) -> Self
+    where
+        O: Into<Option<Duration>>,
+    {
+        self.stats_interval = stats_interval.into();
+        self
+    }
+
     pub fn build(self) -> Crawler {
-        Crawler::new(
-            self.delay,
-            self.crawling_concurrency,
-            self.processing_concurrency,
-            self.crawling_queue_capacity
+        let mut downloader = self
+            .downloader
+            .unwrap_or_else(|| Arc::new(HttpDownloader::new()));
+        if !self.downloader_middlewares.is_empty() {
+            downloader = Arc::new(MiddlewareDownloader::new(
+                downloader,
+                self.downloader_middlewares,
+            ));
+        }
+
+        Crawler {
+            barrier: Arc::new(Barrier::new(3)),
+            crawling_concurrency: self.crawling_concurrency,
+            crawling_queue_capacity: self
+                .crawling_queue_capacity
                 .unwrap_or(self.crawling_concurrency * 400),
-            self.processing_queue_capacity
+            downloader,
+            dupe_filter: self.dupe_filter,
+            handle: CrawlHandle::new(),
+            item_pipelines: self.item_pipelines,
+            job_dir: self.job_dir,
+            limits: self.limits,
+            politeness: self.politeness,
+            persist_interval: self.persist_interval,
+            processing_concurrency: self.processing_concurrency,
+            retry: self.retry,
+            robots_user_agent: self.robots_user_agent,
+            scheduler: self.scheduler,
+            processing_queue_capacity: self
+                .processing_queue_capacity
                 .unwrap_or(self.processing_concurrency * 10),
-        )
+            shutdown_timeout: self.shutdown_timeout,
+            stats: self.stats,
+            stats_interval: self.stats_interval,
+        }
     }
 }
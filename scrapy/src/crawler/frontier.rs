@@ -0,0 +1,145 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use tokio::time::Instant;
+
+use crate::{QueuedRequest, Scheduler};
+
+/// A request waiting for its retry backoff to elapse.
+struct Delayed {
+    ready_at: Instant,
+    seq: u64,
+    queued: QueuedRequest,
+}
+
+impl PartialEq for Delayed {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Delayed {}
+
+impl PartialOrd for Delayed {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Delayed {
+    // Reversed, so the max-heap yields the earliest deadline first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.ready_at, other.seq).cmp(&(self.ready_at, self.seq))
+    }
+}
+
+/// Bookkeeping for the requests a crawl still has to handle.
+///
+/// Every request is either queued in the scheduler, waiting to be handed to
+/// the scraper, delayed, waiting for its retry backoff, due for a retry once
+/// its backoff has elapsed, or in flight, meaning it was handed over and its
+/// result has not come back yet. The crawl is finished exactly when all four
+/// are empty.
+pub(crate) struct Frontier {
+    queue: Box<dyn Scheduler>,
+    delayed: BinaryHeap<Delayed>,
+    retries: VecDeque<QueuedRequest>,
+    next_seq: u64,
+    in_flight: usize,
+}
+
+impl Frontier {
+    pub fn new(queue: Box<dyn Scheduler>) -> Self {
+        Self {
+            queue,
+            delayed: BinaryHeap::new(),
+            retries: VecDeque::new(),
+            next_seq: 0,
+            in_flight: 0,
+        }
+    }
+
+    pub fn push(&mut self, queued: QueuedRequest) {
+        self.queue.push(queued);
+    }
+
+    /// Queues `queued` again once `ready_at` has passed.
+    pub fn push_delayed(&mut self, queued: QueuedRequest, ready_at: Instant) {
+        self.delayed.push(Delayed {
+            ready_at,
+            seq: self.next_seq,
+            queued,
+        });
+        self.next_seq += 1;
+    }
+
+    /// Returns when the next delayed request is due, if there is one.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.delayed.peek().map(|delayed| delayed.ready_at)
+    }
+
+    /// Makes the delayed requests that are due ready to be retried.
+    pub fn release_due(&mut self) {
+        let now = Instant::now();
+        while self
+            .delayed
+            .peek()
+            .is_some_and(|delayed| delayed.ready_at <= now)
+        {
+            if let Some(delayed) = self.delayed.pop() {
+                self.retries.push_back(delayed.queued);
+            }
+        }
+    }
+
+    /// Takes the next request due for a retry, or else the next queued
+    /// request, and marks it as in flight.
+    pub fn pop(&mut self) -> Option<QueuedRequest> {
+        let queued = self.retries.pop_front().or_else(|| self.queue.pop())?;
+        self.in_flight += 1;
+        Some(queued)
+    }
+
+    /// Takes the next request due for a retry and marks it as in flight.
+    pub fn pop_retry(&mut self) -> Option<QueuedRequest> {
+        let queued = self.retries.pop_front()?;
+        self.in_flight += 1;
+        Some(queued)
+    }
+
+    /// Marks an in-flight request as handled.
+    pub fn complete(&mut self) {
+        self.in_flight -= 1;
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    pub fn has_in_flight(&self) -> bool {
+        self.in_flight > 0
+    }
+
+    /// Returns `true` if a request can be handed to the scraper right away.
+    pub fn has_ready(&self) -> bool {
+        self.has_retry() || !self.queue.is_empty()
+    }
+
+    /// Returns `true` if a request due for a retry can be handed to the
+    /// scraper right away.
+    pub fn has_retry(&self) -> bool {
+        !self.retries.is_empty()
+    }
+
+    /// Returns `true` if a request is waiting for its retry backoff or due
+    /// for a retry.
+    pub fn has_pending_retry(&self) -> bool {
+        self.has_retry() || !self.delayed.is_empty()
+    }
+
+    pub fn has_queued(&self) -> bool {
+        self.has_ready() || !self.delayed.is_empty()
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+use super::CloseReason;
+
+/// The lifecycle state of a crawl, as requested through a [`CrawlHandle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CrawlState {
+    Running,
+    Paused,
+    Stopping(CloseReason),
+}
+
+impl CrawlState {
+    pub fn is_stopping(&self) -> bool {
+        matches!(self, Self::Stopping(_))
+    }
+}
+
+/// A cloneable handle for controlling a crawl from another task.
+///
+/// Obtained from [`Crawler::handle`](crate::Crawler::handle) before or while
+/// [`Crawler::crawl`](crate::Crawler::crawl) is running.
+#[derive(Clone)]
+pub struct CrawlHandle {
+    state: Arc<watch::Sender<CrawlState>>,
+}
+
+impl CrawlHandle {
+    pub(crate) fn new() -> Self {
+        let (state, _) = watch::channel(CrawlState::Running);
+
+        Self {
+            state: Arc::new(state),
+        }
+    }
+
+    /// Requests a graceful stop of the crawl.
+    ///
+    /// No new URLs are dequeued after this call. Scrapes already in flight are
+    /// given the crawler's shutdown timeout to finish, and items already
+    /// scraped are still processed before `crawl` returns.
+    pub fn stop(&self) {
+        self.close(CloseReason::Cancelled);
+    }
+
+    /// Stops the crawl for `reason`, unless it is already stopping.
+    pub(crate) fn close(&self, reason: CloseReason) {
+        self.state.send_if_modified(|state| {
+            let stopping = state.is_stopping();
+            if !stopping {
+                *state = CrawlState::Stopping(reason);
+            }
+            !stopping
+        });
+    }
+
+    /// Pauses a running crawl.
+    ///
+    /// No new URLs are dequeued while paused, but scrapes already in flight
+    /// complete and scraped items keep being processed. The visited set and
+    /// the queued URLs are kept, so [`resume`](Self::resume) carries on where
+    /// the crawl left off.
+    pub fn pause(&self) {
+        self.state.send_if_modified(|state| {
+            let running = *state == CrawlState::Running;
+            if running {
+                *state = CrawlState::Paused;
+            }
+            running
+        });
+    }
+
+    /// Resumes a paused crawl.
+    pub fn resume(&self) {
+        self.state.send_if_modified(|state| {
+            let paused = *state == CrawlState::Paused;
+            if paused {
+                *state = CrawlState::Running;
+            }
+            paused
+        });
+    }
+
+    /// Returns `true` once a stop has been requested.
+    pub fn is_stopped(&self) -> bool {
+        self.state.borrow().is_stopping()
+    }
+
+    /// Returns `true` while the crawl is paused.
+    pub fn is_paused(&self) -> bool {
+        *self.state.borrow() == CrawlState::Paused
+    }
+
+    /// Returns why the crawl is stopping, if it is.
+    pub(crate) fn close_reason(&self) -> Option<CloseReason> {
+        match *self.state.borrow() {
+            CrawlState::Stopping(reason) => Some(reason),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<CrawlState> {
+        self.state.subscribe()
+    }
+
+    /// Waits until a stop has been requested.
+    pub(crate) async fn stopped(&self) {
+        let mut state = self.subscribe();
+        let _ = state.wait_for(CrawlState::is_stopping).await;
+    }
+
+    /// Waits until the crawl is no longer paused.
+    pub(crate) async fn unpaused(&self) {
+        let mut state = self.subscribe();
+        let _ = state.wait_for(|state| *state != CrawlState::Paused).await;
+    }
+
+    pub(crate) fn reset(&self) {
+        self.state.send_replace(CrawlState::Running);
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{DupeFilter, QueuedRequest, Request};
+
+const SEEN_FILE: &str = "requests.seen";
+const QUEUE_FILE: &str = "requests.queue";
+const LOG_FILE: &str = "requests.log";
+
+/// Persists the state of a crawl so it can be resumed after a crash.
+///
+/// The directory holds three files:
+///
+/// * `requests.seen` lists every request ever queued, one per line. It is
+///   only ever appended to.
+/// * `requests.queue` lists the pending requests as of the last checkpoint,
+///   with their depth.
+/// * `requests.log` journals the requests queued (`+ request`) and scraped
+///   (`- fingerprint`) since the last checkpoint.
+///
+/// Requests are stored as JSON, one per line.
+///
+/// Every change is written to disk as it happens, so a killed crawl loses
+/// nothing. Checkpoints only fold the journal into `requests.queue` to keep
+/// it short.
+pub(crate) struct JobDir {
+    path: PathBuf,
+    seen: File,
+    log: File,
+    pending: HashMap<u128, (u64, QueuedRequest)>,
+    next_seq: u64,
+}
+
+impl JobDir {
+    /// Opens the job directory at `path`, creating it if needed.
+    ///
+    /// Every request seen by previous runs is fed to `dupe_filter`, and the
+    /// requests they left pending are returned in queueing order.
+    pub fn open(
+        path: &Path,
+        dupe_filter: &mut dyn DupeFilter,
+    ) -> io::Result<(Self, Vec<QueuedRequest>)> {
+        fs::create_dir_all(path)?;
+
+        for entry in read_lines(&path.join(SEEN_FILE))? {
+            match serde_json::from_str::<Request>(&entry) {
+                Ok(request) => {
+                    dupe_filter.request_seen(&request);
+                }
+                Err(_) => log::warn!("ignoring malformed seen request: {}", entry),
+            }
+        }
+
+        let mut job = Self {
+            path: path.to_path_buf(),
+            seen: append(&path.join(SEEN_FILE))?,
+            log: append(&path.join(LOG_FILE))?,
+            pending: HashMap::new(),
+            next_seq: 0,
+        };
+
+        for entry in read_lines(&path.join(QUEUE_FILE))? {
+            match serde_json::from_str(&entry) {
+                Ok(queued) => job.insert_pending(queued),
+                Err(_) => log::warn!("ignoring malformed job queue entry: {}", entry),
+            }
+        }
+
+        // Replaying the journal on top of the queue is idempotent, so a crash
+        // between writing the queue and truncating the journal is harmless.
+        for entry in read_lines(&path.join(LOG_FILE))? {
+            let queued = entry
+                .strip_prefix("+ ")
+                .and_then(|queued| serde_json::from_str(queued).ok());
+            let completed = entry
+                .strip_prefix("- ")
+                .and_then(|fingerprint| u128::from_str_radix(fingerprint, 16).ok());
+            match (queued, completed) {
+                (Some(queued), _) => job.insert_pending(queued),
+                (None, Some(fingerprint)) => {
+                    job.pending.remove(&fingerprint);
+                }
+                _ => log::warn!("ignoring malformed job log entry: {}", entry),
+            }
+        }
+
+        job.checkpoint()?;
+        let pending = job.pending_in_order();
+
+        Ok((job, pending))
+    }
+
+    /// Records a newly queued request.
+    pub fn queued(&mut self, queued: &QueuedRequest) -> io::Result<()> {
+        let request = serde_json::to_string(&queued.request)?;
+        self.seen.write_all(format!("{}\n", request).as_bytes())?;
+        let entry = serde_json::to_string(queued)?;
+        self.log.write_all(format!("+ {}\n", entry).as_bytes())?;
+        self.insert_pending(queued.clone());
+        Ok(())
+    }
+
+    /// Records that a queued request has been scraped.
+    pub fn completed(&mut self, request: &Request) -> io::Result<()> {
+        let fingerprint = request.fingerprint();
+        self.log
+            .write_all(format!("- {:032x}\n", fingerprint).as_bytes())?;
+        self.pending.remove(&fingerprint);
+        Ok(())
+    }
+
+    /// Writes the pending requests to `requests.queue` and clears the journal.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        let queue = self.path.join(QUEUE_FILE);
+        let tmp = queue.with_extension("tmp");
+
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        for queued in self.pending_in_order() {
+            writeln!(writer, "{}", serde_json::to_string(&queued)?)?;
+        }
+        writer.into_inner()?.sync_all()?;
+
+        fs::rename(&tmp, &queue)?;
+        self.log.set_len(0)?;
+
+        Ok(())
+    }
+
+    fn insert_pending(&mut self, queued: QueuedRequest) {
+        let fingerprint = queued.request.fingerprint();
+        if !self.pending.contains_key(&fingerprint) {
+            self.pending.insert(fingerprint, (self.next_seq, queued));
+            self.next_seq += 1;
+        }
+    }
+
+    fn pending_in_order(&self) -> Vec<QueuedRequest> {
+        let mut pending = self.pending.iter().collect::<Vec<_>>();
+        pending.sort_by_key(|(_, (seq, _))| *seq);
+        pending
+            .into_iter()
+            .map(|(_, (_, queued))| queued.clone())
+            .collect()
+    }
+}
+
+fn append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn read_lines(path: &Path) -> io::Result<Vec<String>> {
+    match File::open(path) {
+        Ok(file) => BufReader::new(file)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+            .collect(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+/// Limits that close a crawl before it runs out of URLs.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct CrawlLimits {
+    pub max_depth: Option<usize>,
+    pub max_pages: Option<usize>,
+    pub max_items: Option<usize>,
+    pub max_errors: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+impl CrawlLimits {
+    /// Returns `true` if URLs `depth` links away from the start URLs may be crawled.
+    pub fn allows_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+
+    pub fn pages_reached(&self, pages: usize) -> bool {
+        reached(self.max_pages, pages)
+    }
+
+    pub fn items_reached(&self, items: usize) -> bool {
+        reached(self.max_items, items)
+    }
+
+    pub fn errors_reached(&self, errors: usize) -> bool {
+        reached(self.max_errors, errors)
+    }
+}
+
+fn reached(limit: Option<usize>, count: usize) -> bool {
+    limit.is_some_and(|limit| count >= limit)
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{Downloader, DownloaderMiddleware, Error, Request, Response};
+
+/// The crawl's downloader wrapped in its downloader middlewares.
+pub struct MiddlewareDownloader {
+    downloader: Arc<dyn Downloader>,
+    middlewares: Vec<Arc<dyn DownloaderMiddleware>>,
+}
+
+impl MiddlewareDownloader {
+    pub fn new(
+        downloader: Arc<dyn Downloader>,
+        middlewares: Vec<Arc<dyn DownloaderMiddleware>>,
+    ) -> Self {
+        Self {
+            downloader,
+            middlewares,
+        }
+    }
+
+    /// Passes the request through every `process_request` hook and downloads
+    /// it, unless a middleware answers it first.
+    async fn download(&self, request: &mut Request) -> Result<Response, Error> {
+        for middleware in &self.middlewares {
+            if let Some(response) = middleware.process_request(request).await? {
+                return Ok(response);
+            }
+        }
+
+        self.downloader.fetch(request).await
+    }
+
+    /// Passes a failed download through the `process_error` hooks, in reverse
+    /// order, until one of them recovers from it.
+    async fn recover(&self, request: &Request, mut error: Error) -> Result<Response, Error> {
+        for middleware in self.middlewares.iter().rev() {
+            match middleware.process_error(request, error).await {
+                Ok(response) => return Ok(response),
+                Err(err) => error = err,
+            }
+        }
+
+        Err(error)
+    }
+}
+
+#[async_trait]
+impl Downloader for MiddlewareDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let mut request = request.clone();
+        let mut response = match self.download(&mut request).await {
+            Ok(response) => response,
+            Err(err) => self.recover(&request, err).await?,
+        };
+
+        for middleware in self.middlewares.iter().rev() {
+            response = middleware.process_response(response).await?;
+        }
+
+        Ok(response)
+    }
+}
@@ -1,119 +1,459 @@
 use std::{
-    collections::HashSet,
     fmt::Display,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
-    time::Duration,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use tokio::{
     sync::{mpsc, Barrier},
-    time::sleep,
+    time,
 };
+use url::Url;
 
-use crate::Spider;
+use crate::{
+    urljoin, Downloader, DupeFilter, Error, QueuedRequest, Scheduler, Spider, StatsCollector,
+};
 
-use self::{processor::Processor, scraper::Scraper};
+use self::{
+    frontier::Frontier,
+    handle::CrawlState,
+    job_dir::JobDir,
+    limits::CrawlLimits,
+    pipelines::{ErasedPipeline, Pipelines},
+    processor::Processor,
+    report::CrawlStats,
+    robots::{Fetched, Robots, Verdict},
+    scraper::{ScrapeOutcome, Scraper, ScraperContext},
+    slots::{Politeness, Slots},
+};
 
 pub use crawler_builder::CrawlerBuilder;
+pub use handle::CrawlHandle;
+pub use report::{CloseReason, CrawlReport, ScrapeFailure};
+pub use retry::RetryPolicy;
+pub use slots::{DownloadSlot, SlotKey};
 
 mod crawler_builder;
+mod frontier;
+mod handle;
+mod job_dir;
+mod limits;
+mod middleware;
+mod pipelines;
 mod processor;
+mod report;
+mod retry;
+mod robots;
 mod scraper;
+mod slots;
 mod url_processor;
 
+type DupeFilterFactory = Arc<dyn Fn() -> Box<dyn DupeFilter> + Send + Sync>;
+type SchedulerFactory = Arc<dyn Fn() -> Box<dyn Scheduler> + Send + Sync>;
+
 pub struct Crawler {
-    active_spiders: Arc<AtomicUsize>,
     barrier: Arc<Barrier>,
     crawling_concurrency: usize,
     crawling_queue_capacity: usize,
-    delay: Duration,
+    downloader: Arc<dyn Downloader>,
+    dupe_filter: DupeFilterFactory,
+    handle: CrawlHandle,
+    item_pipelines: Vec<ErasedPipeline>,
+    job_dir: Option<PathBuf>,
+    limits: CrawlLimits,
+    persist_interval: Duration,
     processing_concurrency: usize,
+    politeness: Politeness,
     processing_queue_capacity: usize,
+    retry: Option<RetryPolicy>,
+    robots_user_agent: Option<String>,
+    scheduler: SchedulerFactory,
+    shutdown_timeout: Duration,
+    stats: StatsCollector,
+    stats_interval: Option<Duration>,
 }
 
 impl Crawler {
-    pub(crate) fn new(
-        delay: Duration,
-        crawling_concurrency: usize,
-        processing_concurrency: usize,
-        crawling_queue_capacity: usize,
-        processing_queue_capacity: usize,
-    ) -> Self {
-        let active_spiders = Arc::new(AtomicUsize::new(0));
-        let barrier = Arc::new(Barrier::new(3));
-
-        Self {
-            active_spiders,
-            barrier,
-            crawling_concurrency,
-            crawling_queue_capacity,
-            delay,
-            processing_concurrency,
-            processing_queue_capacity,
-        }
+    /// Returns a handle that can stop, pause and resume this crawler's crawls
+    /// from another task.
+    pub fn handle(&self) -> CrawlHandle {
+        self.handle.clone()
     }
 
-    pub async fn crawl<T, E, S>(&self, spider: S)
+    /// Returns the collector this crawler's crawls record their stats in,
+    /// which adds up the stats of every crawl, see [`StatsCollector`].
+    pub fn stats(&self) -> StatsCollector {
+        self.stats.clone()
+    }
+
+    /// Crawls `spider` until every reachable URL was scraped or the crawl is
+    /// closed, and waits for its items to go through the item pipelines.
+    ///
+    /// # Returns
+    ///
+    /// A report of the crawl. Nothing is crawled if an item pipeline cannot
+    /// process the spider's items, which returns an
+    /// [`Error::PipelineItemType`], or if the job directory cannot be opened,
+    /// which returns an [`Error::Io`].
+    pub async fn crawl<T, E, S>(&self, spider: S) -> Result<CrawlReport, Error>
     where
         T: Send + 'static,
         E: Display + Send + 'static,
         S: Spider<Item = T, Error = E> + 'static,
     {
+        let started_at = Instant::now();
+        let deadline = self
+            .limits
+            .timeout
+            .map(|timeout| time::Instant::now() + timeout);
+        let stats = Arc::new(CrawlStats::new(self.stats.clone()));
         let spider_arc = Arc::new(spider);
 
-        let mut visited_urls = HashSet::<String>::new();
+        let pipelines = Arc::new(Pipelines::new(&self.item_pipelines)?);
+        let mut dupe_filter = (self.dupe_filter)();
+        let (mut job, pending) = self.open_job_dir(dupe_filter.as_mut())?;
+
+        if let Err(err) = pipelines.open(&spider_arc.name()).await {
+            log::error!("cannot open the item pipelines: {}", err);
+            return Ok(stats.report(
+                started_at.elapsed(),
+                Duration::ZERO,
+                CloseReason::PipelineFailed,
+            ));
+        }
+
+        let slots = Arc::new(Slots::new(self.politeness.clone()));
+        let mut robots = self.robots_user_agent.clone().map(|user_agent| {
+            Robots::new(
+                user_agent,
+                self.downloader.clone(),
+                slots.clone(),
+                stats.clone(),
+            )
+        });
 
-        let (urls_to_visit_tx, urls_to_visit_rx) =
-            mpsc::channel::<String>(self.crawling_queue_capacity);
+        let mut frontier = Frontier::new((self.scheduler)());
+        for queued in pending {
+            admit(
+                queued,
+                &mut robots,
+                &mut frontier,
+                &mut job,
+                &self.handle,
+                &stats,
+            );
+        }
+
+        let (requests_to_visit_tx, requests_to_visit_rx) =
+            mpsc::channel::<QueuedRequest>(self.crawling_queue_capacity);
         let (items_tx, items_rx) = mpsc::channel(self.processing_queue_capacity);
-        let (new_urls_tx, mut new_urls_rx) = mpsc::channel(self.crawling_queue_capacity);
+        let (outcomes_tx, mut outcomes_rx) = mpsc::channel(self.crawling_queue_capacity);
+
+        for mut request in spider_arc.start_requests() {
+            let absolute_url = Url::parse(request.url.trim())
+                .map(String::from)
+                .map_err(Error::from);
+            let Some(url) = normalize_url(absolute_url, &request.url) else {
+                continue;
+            };
+            request.url = url;
 
-        for url in spider_arc.start_urls() {
-            visited_urls.insert(url.clone());
-            let _ = urls_to_visit_tx.send(url).await;
+            if !dupe_filter.request_seen(&request) {
+                let queued = QueuedRequest::new(request, 0);
+                stats.request_queued(queued.depth);
+                persist(&mut job, &self.handle, |job| job.queued(&queued));
+                admit(
+                    queued,
+                    &mut robots,
+                    &mut frontier,
+                    &mut job,
+                    &self.handle,
+                    &stats,
+                );
+            }
         }
 
-        let processor = Processor::new(self.processing_concurrency, self.barrier.clone());
-        processor.process_items(spider_arc.clone(), items_rx);
+        let processor = Processor::new(
+            self.processing_concurrency,
+            self.barrier.clone(),
+            self.handle.clone(),
+            self.limits,
+            stats.clone(),
+        );
+        processor.process_items(pipelines, items_rx);
 
         let scraper = Scraper::new(
-            self.active_spiders.clone(),
-            self.barrier.clone(),
             self.crawling_concurrency,
-            self.delay,
+            self.shutdown_timeout,
             spider_arc.clone(),
+            ScraperContext {
+                barrier: self.barrier.clone(),
+                downloader: self.downloader.clone(),
+                handle: self.handle.clone(),
+                limits: self.limits,
+                retry: self.retry,
+                slots: slots.clone(),
+                stats: stats.clone(),
+            },
         );
 
-        scraper.scrape_urls(urls_to_visit_rx, new_urls_tx.clone(), items_tx);
+        scraper.scrape_requests(requests_to_visit_rx, outcomes_tx, items_tx);
+
+        let mut state_rx = self.handle.subscribe();
+        let mut state = *state_rx.borrow_and_update();
+        let mut paused_since = (state == CrawlState::Paused).then(Instant::now);
+        let mut paused = Duration::ZERO;
+
+        let mut persist_interval = tokio::time::interval(self.persist_interval);
+        persist_interval.reset();
 
-        loop {
-            if let Ok((visited_url, new_urls)) = new_urls_rx.try_recv() {
-                visited_urls.insert(visited_url);
+        let mut stats_interval = self.stats_interval.map(|period| {
+            let mut interval = time::interval(period);
+            interval.reset();
+            interval
+        });
 
-                for url in new_urls {
-                    if !visited_urls.contains(&url) {
-                        visited_urls.insert(url.clone());
-                        log::debug!("queueing: {}", url);
-                        let _ = urls_to_visit_tx.send(url).await;
+        let mut dispatched = 0;
+        self.close_if_pages_done(dispatched, &frontier);
+
+        // Every queued request is either parked waiting for its robots.txt,
+        // waiting in the frontier or in flight until its result comes back on
+        // `outcomes_rx`, so the crawl is over exactly when none are left. Once
+        // stopping, parked and queued requests are abandoned and only
+        // in-flight ones are waited for.
+        while frontier.has_in_flight()
+            || (!state.is_stopping()
+                && (frontier.has_queued() || robots.as_ref().is_some_and(Robots::is_fetching)))
+        {
+            tokio::select! {
+                result = outcomes_rx.recv() => {
+                    let Some(outcome) = result else {
+                        log::error!("scraper stopped before the crawl finished");
+                        break;
+                    };
+
+                    frontier.complete();
+
+                    let (scraped, base_url, depth, mut new_requests) = match outcome {
+                        ScrapeOutcome::Visited { request, base_url, depth, new_requests } => {
+                            (request, base_url, depth, new_requests)
+                        }
+                        // Retried and skipped requests stay pending in the job
+                        // directory, so a resumed crawl picks them up again.
+                        ScrapeOutcome::Retry(queued) => {
+                            let retry = self.retry.unwrap_or_default();
+                            let backoff = retry.backoff_after(queued.attempts);
+                            log::info!("retrying {} in {:?}", queued.request.url, backoff);
+                            stats.retried();
+                            frontier.push_delayed(queued, time::Instant::now() + backoff);
+                            continue;
+                        }
+                        ScrapeOutcome::Skipped => continue,
+                    };
+
+                    // Requests beyond the maximum depth are dropped before the
+                    // dupe filter sees them, so they can still be queued if
+                    // found again closer to the start requests.
+                    let depth = depth + 1;
+                    if !self.limits.allows_depth(depth) {
+                        new_requests.clear();
                     }
+
+                    for mut request in new_requests {
+                        let absolute_url = urljoin(&base_url, &request.url);
+                        let Some(url) = normalize_url(absolute_url, &request.url) else {
+                            continue;
+                        };
+                        request.url = url;
+
+                        if !dupe_filter.request_seen(&request) {
+                            log::debug!("queueing: {}", request.url);
+                            let queued = QueuedRequest::new(request, depth);
+                            stats.request_queued(depth);
+                            persist(&mut job, &self.handle, |job| job.queued(&queued));
+                            admit(queued, &mut robots, &mut frontier, &mut job, &self.handle, &stats);
+                        }
+                    }
+
+                    persist(&mut job, &self.handle, |job| job.completed(&scraped));
+                    self.close_if_pages_done(dispatched, &frontier);
                 }
-            }
+                // Once enough pages were dispatched, only retries are, as they
+                // don't count as new pages.
+                Ok(permit) = requests_to_visit_tx.reserve(),
+                    if state == CrawlState::Running
+                        && frontier.in_flight() < self.crawling_queue_capacity
+                        && (frontier.has_retry()
+                            || (frontier.has_ready() && !self.limits.pages_reached(dispatched))) =>
+                {
+                    let queued = if self.limits.pages_reached(dispatched) {
+                        frontier.pop_retry()
+                    } else {
+                        frontier.pop()
+                    };
+                    if let Some(queued) = queued {
+                        if queued.attempts == 0 {
+                            dispatched += 1;
+                        }
+                        permit.send(queued);
+                    }
+                }
+                Ok(()) = state_rx.changed() => {
+                    state = *state_rx.borrow_and_update();
+                    log::info!("crawl {:?}", state);
 
-            if new_urls_tx.capacity() == self.crawling_queue_capacity
-                && urls_to_visit_tx.capacity() == self.crawling_queue_capacity
-                && self.active_spiders.load(Ordering::SeqCst) == 0
-            {
-                break;
-            }
+                    if state == CrawlState::Paused {
+                        paused_since.get_or_insert_with(Instant::now);
+                    } else if let Some(since) = paused_since.take() {
+                        paused += since.elapsed();
+                    }
+                }
+                _ = persist_interval.tick(), if job.is_some() => {
+                    persist(&mut job, &self.handle, JobDir::checkpoint);
+                }
+                () = tick(&mut stats_interval) => {
+                    stats.dump();
+                }
+                Some(fetched) = robots_fetched(&mut robots) => {
+                    let Fetched { host, crawl_delay, parked } = fetched;
+                    if let Some(crawl_delay) = crawl_delay {
+                        slots.crawl_delay(&host, crawl_delay);
+                    }
 
-            sleep(Duration::from_millis(5)).await;
+                    for queued in parked {
+                        admit(queued, &mut robots, &mut frontier, &mut job, &self.handle, &stats);
+                    }
+                }
+                () = sleep_until(frontier.next_due()) => {
+                    frontier.release_due();
+                }
+                () = sleep_until(deadline), if !state.is_stopping() => {
+                    self.handle.close(CloseReason::Timeout);
+                }
+            }
         }
 
-        drop(urls_to_visit_tx);
+        persist(&mut job, &self.handle, JobDir::checkpoint);
+
+        paused += paused_since.map_or(Duration::ZERO, |since| since.elapsed());
+
+        drop(requests_to_visit_tx);
 
         self.barrier.wait().await;
+
+        // The scraper may hand back requests it skipped because of a stop before
+        // the state change above was observed, so ask the handle directly.
+        let close_reason = self.handle.close_reason().unwrap_or_default();
+
+        self.handle.reset();
+
+        stats.dump();
+        Ok(stats.report(started_at.elapsed(), paused, close_reason))
+    }
+
+    /// Closes the crawl once `max_pages` pages were dispatched and all of
+    /// them were handled, retries included.
+    fn close_if_pages_done(&self, dispatched: usize, frontier: &Frontier) {
+        if self.limits.pages_reached(dispatched)
+            && !frontier.has_in_flight()
+            && !frontier.has_pending_retry()
+        {
+            self.handle.close(CloseReason::MaxPages);
+        }
+    }
+
+    /// Opens the configured job directory, restoring the seen requests into
+    /// `dupe_filter` and returning the pending ones.
+    fn open_job_dir(
+        &self,
+        dupe_filter: &mut dyn DupeFilter,
+    ) -> Result<(Option<JobDir>, Vec<QueuedRequest>), Error> {
+        let Some(path) = self.job_dir.as_ref() else {
+            return Ok((None, Vec::new()));
+        };
+
+        let (job, pending) = JobDir::open(path, dupe_filter).inspect_err(|err| {
+            log::error!("cannot open job directory {}: {}", path.display(), err)
+        })?;
+        log::info!("resuming job {}: {} pending", path.display(), pending.len());
+        Ok((Some(job), pending))
+    }
+}
+
+/// Unwraps a resolved URL, logging URLs that could not be resolved. Requests
+/// are queued and fetched with their URL as resolved, and only deduplicated
+/// by its canonical form.
+fn normalize_url(normalized: Result<String, Error>, url: &str) -> Option<String> {
+    normalized
+        .map_err(|err| log::warn!("skipping {}: {}", url, err))
+        .ok()
+}
+
+/// Pushes `queued` to the frontier unless robots.txt disallows it. Requests
+/// whose robots.txt is still being fetched are parked until it arrives.
+fn admit(
+    queued: QueuedRequest,
+    robots: &mut Option<Robots>,
+    frontier: &mut Frontier,
+    job: &mut Option<JobDir>,
+    handle: &CrawlHandle,
+    stats: &CrawlStats,
+) {
+    let Some(robots) = robots else {
+        frontier.push(queued);
+        return;
+    };
+
+    match robots.check(queued) {
+        Verdict::Allowed(queued) => frontier.push(queued),
+        Verdict::Disallowed(queued) => {
+            log::debug!("forbidden by robots.txt: {}", queued.request.url);
+            stats.robots_blocked();
+            persist(job, handle, |job| job.completed(&queued.request));
+        }
+        Verdict::Parked => {}
+    }
+}
+
+/// Waits for the next robots.txt to be fetched, or forever if none is.
+async fn robots_fetched(robots: &mut Option<Robots>) -> Option<Fetched> {
+    match robots {
+        Some(robots) if robots.is_fetching() => Some(robots.fetched().await),
+        _ => std::future::pending().await,
+    }
+}
+
+/// Waits until `deadline`, or forever if there is none.
+async fn sleep_until(deadline: Option<time::Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Waits for the next tick of `interval`, or forever if there is none.
+async fn tick(interval: &mut Option<time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Applies `write` to the job directory, if any. A failed write closes the
+/// crawl, as a resumed crawl could no longer tell which requests are done,
+/// and stops writing to the job directory.
+fn persist<F>(job: &mut Option<JobDir>, handle: &CrawlHandle, write: F)
+where
+    F: FnOnce(&mut JobDir) -> std::io::Result<()>,
+{
+    if let Some(dir) = job {
+        if let Err(err) = write(dir) {
+            log::error!("cannot persist crawl state: {}", err);
+            handle.close(CloseReason::JobDirFailed);
+            *job = None;
+        }
     }
 }
@@ -0,0 +1,96 @@
+use std::{
+    any::{self, Any},
+    sync::Arc,
+};
+
+use crate::{Error, ItemError, ItemPipeline};
+
+/// An item pipeline registered on the builder, with its item type erased so
+/// pipelines for any item type can be stored side by side.
+#[derive(Clone)]
+pub struct ErasedPipeline {
+    item_type: &'static str,
+    pipeline: Arc<dyn Any + Send + Sync>,
+}
+
+impl ErasedPipeline {
+    pub fn new<T, P>(pipeline: P) -> Self
+    where
+        T: 'static,
+        P: ItemPipeline<T> + 'static,
+    {
+        let pipeline: Arc<dyn ItemPipeline<T>> = Arc::new(pipeline);
+
+        Self {
+            item_type: any::type_name::<T>(),
+            pipeline: Arc::new(pipeline),
+        }
+    }
+}
+
+/// The item pipelines of a crawl, in the order items go through them.
+pub struct Pipelines<T> {
+    stages: Vec<Arc<dyn ItemPipeline<T>>>,
+}
+
+impl<T: 'static> Pipelines<T> {
+    /// Recovers the pipelines for items of type `T`.
+    ///
+    /// # Returns
+    ///
+    /// The pipelines, or an [`Error::PipelineItemType`] if one was registered
+    /// for another item type, as the spider's items could not be handed to
+    /// it.
+    pub fn new(pipelines: &[ErasedPipeline]) -> Result<Self, Error> {
+        let stages = pipelines
+            .iter()
+            .map(|erased| {
+                erased
+                    .pipeline
+                    .downcast_ref::<Arc<dyn ItemPipeline<T>>>()
+                    .cloned()
+                    .ok_or_else(|| Error::PipelineItemType {
+                        pipeline: erased.item_type,
+                        item: any::type_name::<T>(),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { stages })
+    }
+
+    /// Opens every pipeline in order. If one fails to open, the ones already
+    /// opened are closed again.
+    pub async fn open(&self, spider: &str) -> Result<(), Error> {
+        for (opened, stage) in self.stages.iter().enumerate() {
+            if let Err(err) = stage.open(spider).await {
+                close(&self.stages[..opened]).await;
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hands `item` to every stage in turn.
+    pub async fn process_item(&self, mut item: T) -> Result<T, ItemError> {
+        for stage in &self.stages {
+            item = stage.process_item(item).await?;
+        }
+
+        Ok(item)
+    }
+
+    pub async fn close(&self) {
+        close(&self.stages).await;
+    }
+}
+
+/// Closes `stages`, logging the ones that fail to close.
+async fn close<T>(stages: &[Arc<dyn ItemPipeline<T>>]) {
+    for stage in stages {
+        if let Err(err) = stage.close().await {
+            log::error!("{}", err);
+        }
+    }
+}
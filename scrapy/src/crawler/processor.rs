@@ -1,43 +1,81 @@
-use std::{sync::Arc, fmt::Display};
+use std::sync::Arc;
 
 use futures::StreamExt;
 use tokio::sync::{mpsc, Barrier};
 use tokio_stream::wrappers::ReceiverStream;
 
-use crate::Spider;
+use crate::ItemError;
+
+use super::{
+    handle::CrawlHandle,
+    limits::CrawlLimits,
+    pipelines::Pipelines,
+    report::{CloseReason, CrawlStats},
+};
 
 pub struct Processor {
     processing_concurrency: usize,
     barrier: Arc<Barrier>,
+    handle: CrawlHandle,
+    limits: CrawlLimits,
+    stats: Arc<CrawlStats>,
 }
 
 impl Processor {
-    pub fn new(processing_concurrency: usize, barrier: Arc<Barrier>) -> Self {
+    pub fn new(
+        processing_concurrency: usize,
+        barrier: Arc<Barrier>,
+        handle: CrawlHandle,
+        limits: CrawlLimits,
+        stats: Arc<CrawlStats>,
+    ) -> Self {
         Self {
             processing_concurrency,
             barrier,
+            handle,
+            limits,
+            stats,
         }
     }
 
-    pub fn process_items<T, E>(
-        &self,
-        spider: Arc<dyn Spider<Item = T, Error = E>>,
-        items_rx: mpsc::Receiver<T>,
-    ) where
+    /// Runs every item through the pipelines, closing them once the items
+    /// run out.
+    pub fn process_items<T>(&self, pipelines: Arc<Pipelines<T>>, items_rx: mpsc::Receiver<T>)
+    where
         T: Send + 'static,
-        E: Display + 'static,
     {
         let processing_concurrency = self.processing_concurrency;
         let barrier = self.barrier.clone();
+        let handle = self.handle.clone();
+        let limits = self.limits;
+        let stats = self.stats.clone();
         tokio::spawn(async move {
             ReceiverStream::new(items_rx)
                 .for_each_concurrent(processing_concurrency, |item| async {
-                    let _ = spider.process(item).await.map_err(|err| {
-                        log::error!("{}", err);
-                    });
+                    match pipelines.process_item(item).await {
+                        Ok(_) => {
+                            stats.item_processed();
+                            if limits.items_reached(stats.items_processed()) {
+                                handle.close(CloseReason::MaxItems);
+                            }
+                        }
+                        Err(ItemError::Dropped(reason)) => {
+                            log::info!("dropped item: {}", reason);
+                            stats.item_dropped();
+                        }
+                        Err(err) => {
+                            log::error!("{}", err);
+                            stats.process_failed();
+                            if limits.errors_reached(stats.errors()) {
+                                handle.close(CloseReason::MaxErrors);
+                            }
+                        }
+                    }
                 })
                 .await;
 
+            pipelines.close().await;
+
             barrier.wait().await;
         });
     }
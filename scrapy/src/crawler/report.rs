@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{Response, Stats, StatsCollector};
+
+/// Why a crawl finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CloseReason {
+    /// Every reachable URL was crawled.
+    #[default]
+    Finished,
+
+    /// A stop was requested through a [`CrawlHandle`](crate::CrawlHandle).
+    Cancelled,
+
+    /// The maximum number of pages was fetched.
+    MaxPages,
+
+    /// The maximum number of items was processed.
+    MaxItems,
+
+    /// The maximum number of errors occurred.
+    MaxErrors,
+
+    /// The crawl ran for longer than its timeout.
+    Timeout,
+
+    /// An item pipeline failed to open, so the crawl did not start.
+    PipelineFailed,
+
+    /// Writing the crawl state to the job directory failed, so the crawl
+    /// stopped rather than run on without being resumable.
+    JobDirFailed,
+}
+
+/// A summary of a finished crawl, returned by [`Crawler::crawl`](crate::Crawler::crawl).
+#[derive(Debug, Clone, Default)]
+pub struct CrawlReport {
+    /// The number of URLs handed to the spider, not counting retries.
+    pub pages_visited: usize,
+
+    /// The number of URLs the spider failed to scrape, including failed
+    /// downloads and unknown callbacks.
+    pub scrape_errors: usize,
+
+    /// The number of failed scrapes that were retried.
+    pub retries: usize,
+
+    /// The number of items returned by successful scrapes.
+    pub items_scraped: usize,
+
+    /// The number of items that went through every item pipeline.
+    pub items_processed: usize,
+
+    /// The number of items an item pipeline dropped.
+    pub items_dropped: usize,
+
+    /// The number of items an item pipeline failed.
+    pub process_errors: usize,
+
+    /// The number of URLs dropped because robots.txt disallows them.
+    pub robots_blocked: usize,
+
+    /// The wall-clock time the crawl took, including time spent paused.
+    pub duration: Duration,
+
+    /// The time the crawl spent paused.
+    pub paused: Duration,
+
+    /// Why the crawl finished.
+    pub close_reason: CloseReason,
+
+    /// The URLs given up on after their last scrape attempt failed.
+    pub failed_urls: HashMap<String, ScrapeFailure>,
+
+    /// The crawler's [`StatsCollector`] stats when the crawl finished,
+    /// including those recorded in it before the crawl.
+    pub stats: Stats,
+}
+
+/// Why a URL was given up on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrapeFailure {
+    /// The number of times the URL was scraped.
+    pub attempts: usize,
+
+    /// The error returned by the last attempt.
+    pub error: String,
+}
+
+impl CrawlReport {
+    /// The time the crawl spent actually crawling, excluding time spent paused.
+    pub fn active_duration(&self) -> Duration {
+        self.duration.saturating_sub(self.paused)
+    }
+}
+
+/// Counters shared between the crawler tasks while a crawl is running, also
+/// recorded in the crawler's [`StatsCollector`].
+pub(crate) struct CrawlStats {
+    pages_visited: AtomicUsize,
+    scrape_errors: AtomicUsize,
+    retries: AtomicUsize,
+    items_scraped: AtomicUsize,
+    items_processed: AtomicUsize,
+    items_dropped: AtomicUsize,
+    process_errors: AtomicUsize,
+    robots_blocked: AtomicUsize,
+    failed_urls: Mutex<HashMap<String, ScrapeFailure>>,
+    collector: StatsCollector,
+}
+
+impl CrawlStats {
+    pub fn new(collector: StatsCollector) -> Self {
+        Self {
+            pages_visited: AtomicUsize::new(0),
+            scrape_errors: AtomicUsize::new(0),
+            retries: AtomicUsize::new(0),
+            items_scraped: AtomicUsize::new(0),
+            items_processed: AtomicUsize::new(0),
+            items_dropped: AtomicUsize::new(0),
+            process_errors: AtomicUsize::new(0),
+            robots_blocked: AtomicUsize::new(0),
+            failed_urls: Mutex::new(HashMap::new()),
+            collector,
+        }
+    }
+
+    pub fn page_visited(&self) {
+        self.pages_visited.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Counts a request handed to the downloader, whether for a page or for
+    /// a robots.txt.
+    pub fn request_sent(&self) {
+        self.collector.inc("downloader/request_count");
+    }
+
+    pub fn response_downloaded(&self, response: &Response) {
+        let status = response.status.as_u16();
+        self.collector.inc("downloader/response_count");
+        self.collector
+            .inc(&format!("downloader/response_status_count/{}", status));
+        self.collector
+            .inc_by("downloader/response_bytes", response.body.len() as u64);
+    }
+
+    pub fn request_queued(&self, depth: usize) {
+        self.collector
+            .inc(&format!("request_depth_count/{}", depth));
+        self.collector.set_max("request_depth_max", depth as i64);
+    }
+
+    /// Counts a failed scrape, `kind` being the type of error it failed with.
+    pub fn scrape_failed(&self, kind: &str) {
+        self.scrape_errors.fetch_add(1, Ordering::SeqCst);
+        self.collector.inc(&format!("error_count/{}", kind));
+    }
+
+    pub fn retried(&self) {
+        self.retries.fetch_add(1, Ordering::SeqCst);
+        self.collector.inc("retry_count");
+    }
+
+    pub fn gave_up(&self, url: &str, attempts: usize, error: String) {
+        self.failed_urls
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), ScrapeFailure { attempts, error });
+    }
+
+    /// The number of scrape and process errors so far.
+    pub fn errors(&self) -> usize {
+        self.scrape_errors.load(Ordering::SeqCst) + self.process_errors.load(Ordering::SeqCst)
+    }
+
+    pub fn items_processed(&self) -> usize {
+        self.items_processed.load(Ordering::SeqCst)
+    }
+
+    pub fn items_scraped(&self, count: usize) {
+        self.items_scraped.fetch_add(count, Ordering::SeqCst);
+        self.collector.inc_by("item_scraped_count", count as u64);
+    }
+
+    pub fn item_processed(&self) {
+        self.items_processed.fetch_add(1, Ordering::SeqCst);
+        self.collector.inc("item_processed_count");
+    }
+
+    pub fn item_dropped(&self) {
+        self.items_dropped.fetch_add(1, Ordering::SeqCst);
+        self.collector.inc("item_dropped_count");
+    }
+
+    pub fn process_failed(&self) {
+        self.process_errors.fetch_add(1, Ordering::SeqCst);
+        self.collector.inc("error_count/pipeline");
+    }
+
+    pub fn robots_blocked(&self) {
+        self.robots_blocked.fetch_add(1, Ordering::SeqCst);
+        self.collector.inc("robots_blocked_count");
+    }
+
+    /// Logs the collector's stats.
+    pub fn dump(&self) {
+        log::info!("crawl stats:\n{}", self.collector.snapshot());
+    }
+
+    pub fn report(
+        &self,
+        duration: Duration,
+        paused: Duration,
+        close_reason: CloseReason,
+    ) -> CrawlReport {
+        CrawlReport {
+            pages_visited: self.pages_visited.load(Ordering::SeqCst),
+            scrape_errors: self.scrape_errors.load(Ordering::SeqCst),
+            retries: self.retries.load(Ordering::SeqCst),
+            items_scraped: self.items_scraped.load(Ordering::SeqCst),
+            items_processed: self.items_processed.load(Ordering::SeqCst),
+            items_dropped: self.items_dropped.load(Ordering::SeqCst),
+            process_errors: self.process_errors.load(Ordering::SeqCst),
+            robots_blocked: self.robots_blocked.load(Ordering::SeqCst),
+            duration,
+            paused,
+            close_reason,
+            failed_urls: self.failed_urls.lock().unwrap().clone(),
+            stats: self.collector.snapshot(),
+        }
+    }
+}
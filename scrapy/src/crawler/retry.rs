@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::Error;
+
+/// How failed scrapes are retried.
+///
+/// A failed URL goes back to the frontier and is scraped again once its
+/// backoff has elapsed, so it doesn't hold a concurrency slot while waiting.
+/// Every download error is worth retrying but [`Error::CacheMiss`], which
+/// would miss again. Whether a scrape error is, is decided by
+/// [`Spider::is_retryable`](crate::Spider::is_retryable).
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many times a URL is scraped before giving up, counting the
+    /// first attempt.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the wait before the first retry. Each further retry waits twice
+    /// as long as the previous one.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Caps the wait between two attempts.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets whether waits are randomized between half and all of their
+    /// nominal value, so URLs that failed together don't retry together.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns `true` if a URL that has failed `attempts` times may be retried.
+    pub(crate) fn allows_retry(&self, attempts: usize) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// Returns `true` if a download failing with `err` is worth retrying.
+    pub(crate) fn is_retryable(&self, err: &Error) -> bool {
+        !matches!(err, Error::CacheMiss(_))
+    }
+
+    /// Returns the wait before the attempt following `attempts` failures.
+    pub(crate) fn backoff_after(&self, attempts: usize) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(31) as u32;
+        let backoff = self
+            .backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff);
+
+        if self.jitter && !backoff.is_zero() {
+            rand::thread_rng().gen_range(backoff / 2..=backoff)
+        } else {
+            backoff
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use tokio::sync::mpsc;
+use url::Url;
+
+use crate::{Downloader, QueuedRequest, Request, Response, RobotsTxt};
+
+use super::{report::CrawlStats, slots::Slots};
+
+/// What robots.txt says about a queued request.
+pub(crate) enum Verdict {
+    Allowed(QueuedRequest),
+    Disallowed(QueuedRequest),
+
+    /// The host's robots.txt is still being fetched, and the request is held
+    /// back until it arrives.
+    Parked,
+}
+
+/// A robots.txt that finished fetching, with the requests parked waiting for
+/// it.
+pub(crate) struct Fetched {
+    pub host: String,
+    pub crawl_delay: Option<Duration>,
+    pub parked: Vec<QueuedRequest>,
+}
+
+enum Entry {
+    Fetching(Vec<QueuedRequest>),
+    Fetched(Arc<RobotsTxt>),
+}
+
+/// The per-origin robots.txt cache of a running crawl. Robots.txt files are
+/// downloaded like any other request: through the crawl's downloader and
+/// middlewares, in their host's download slot.
+pub(crate) struct Robots {
+    user_agent: String,
+    downloader: Arc<dyn Downloader>,
+    slots: Arc<Slots>,
+    stats: Arc<CrawlStats>,
+    origins: HashMap<String, Entry>,
+    fetching: usize,
+    fetched_tx: mpsc::UnboundedSender<(String, RobotsTxt)>,
+    fetched_rx: mpsc::UnboundedReceiver<(String, RobotsTxt)>,
+}
+
+impl Robots {
+    pub fn new(
+        user_agent: String,
+        downloader: Arc<dyn Downloader>,
+        slots: Arc<Slots>,
+        stats: Arc<CrawlStats>,
+    ) -> Self {
+        let (fetched_tx, fetched_rx) = mpsc::unbounded_channel();
+
+        Self {
+            user_agent,
+            downloader,
+            slots,
+            stats,
+            origins: HashMap::new(),
+            fetching: 0,
+            fetched_tx,
+            fetched_rx,
+        }
+    }
+
+    /// Checks `queued` against its origin's robots.txt, starting to fetch it
+    /// if this is the first request seen on that origin.
+    pub fn check(&mut self, queued: QueuedRequest) -> Verdict {
+        let Ok(url) = Url::parse(&queued.request.url) else {
+            return Verdict::Allowed(queued);
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return Verdict::Allowed(queued);
+        }
+
+        let origin = url.origin().ascii_serialization();
+        match self.origins.get_mut(&origin) {
+            Some(Entry::Fetched(robots)) => {
+                if robots.is_allowed(&self.user_agent, &path(&url)) {
+                    Verdict::Allowed(queued)
+                } else {
+                    Verdict::Disallowed(queued)
+                }
+            }
+            Some(Entry::Fetching(parked)) => {
+                parked.push(queued);
+                Verdict::Parked
+            }
+            None => {
+                self.fetch(origin.clone());
+                self.origins.insert(origin, Entry::Fetching(vec![queued]));
+                Verdict::Parked
+            }
+        }
+    }
+
+    /// Returns `true` while requests are parked waiting for a robots.txt.
+    pub fn is_fetching(&self) -> bool {
+        self.fetching > 0
+    }
+
+    /// Waits for the next robots.txt to be fetched, caches it and returns the
+    /// requests that were waiting for it, to be checked again.
+    pub async fn fetched(&mut self) -> Fetched {
+        let (origin, robots) = self
+            .fetched_rx
+            .recv()
+            .await
+            .expect("the cache holds a sender");
+
+        self.fetching -= 1;
+        let crawl_delay = robots.crawl_delay(&self.user_agent);
+        let parked = match self
+            .origins
+            .insert(origin.clone(), Entry::Fetched(Arc::new(robots)))
+        {
+            Some(Entry::Fetching(parked)) => parked,
+            _ => Vec::new(),
+        };
+
+        let host = Url::parse(&origin)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+
+        Fetched {
+            host,
+            crawl_delay,
+            parked,
+        }
+    }
+
+    fn fetch(&mut self, origin: String) {
+        self.fetching += 1;
+        let request = Request::new(format!("{}/robots.txt", origin))
+            .header("User-Agent", self.user_agent.as_str());
+        let downloader = self.downloader.clone();
+        let slots = self.slots.clone();
+        let stats = self.stats.clone();
+        let fetched_tx = self.fetched_tx.clone();
+
+        tokio::spawn(async move {
+            let slot = slots.acquire(&request.url).await;
+            stats.request_sent();
+            let response = downloader.fetch(&request).await;
+            drop(slot);
+
+            let robots = match response {
+                Ok(response) => {
+                    stats.response_downloaded(&response);
+                    parse_robots(&response)
+                }
+                Err(err) => {
+                    log::warn!(
+                        "cannot fetch {}, disallowing the site: {}",
+                        request.url,
+                        err
+                    );
+                    RobotsTxt::disallow_all()
+                }
+            };
+            let _ = fetched_tx.send((origin, robots));
+        });
+    }
+}
+
+/// Reads a downloaded robots.txt, following RFC 9309: a missing file allows
+/// everything, and an unreachable one disallows everything.
+fn parse_robots(response: &Response) -> RobotsTxt {
+    let status = response.status;
+    if status.is_client_error() {
+        return RobotsTxt::allow_all();
+    }
+    if !status.is_success() {
+        log::warn!(
+            "cannot fetch {}, disallowing the site: {}",
+            response.url,
+            status
+        );
+        return RobotsTxt::disallow_all();
+    }
+
+    RobotsTxt::parse(&response.text())
+}
+
+/// Returns the part of `url` robots.txt rules are matched against.
+fn path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
@@ -1,31 +1,59 @@
-use std::{
-    fmt::Display,
-    sync::{atomic::AtomicUsize, Arc},
-    time::Duration,
-};
+use std::{fmt::Display, sync::Arc, time::Duration};
 
 use tokio::sync::{mpsc, Barrier};
 
-use crate::Spider;
+use crate::{Downloader, QueuedRequest, Request, Spider};
 
-use super::url_processor::UrlProcessor;
+use super::{
+    handle::CrawlHandle, limits::CrawlLimits, report::CrawlStats, retry::RetryPolicy, slots::Slots,
+    url_processor::UrlProcessor,
+};
 
-struct ScraperContext {
-    active_spiders: Arc<AtomicUsize>,
-    barrier: Arc<Barrier>,
+/// The crawl-wide state shared with the scraping tasks.
+pub struct ScraperContext {
+    pub barrier: Arc<Barrier>,
+    pub downloader: Arc<dyn Downloader>,
+    pub handle: CrawlHandle,
+    pub limits: CrawlLimits,
+    pub retry: Option<RetryPolicy>,
+    pub slots: Arc<Slots>,
+    pub stats: Arc<CrawlStats>,
 }
 
 pub struct Scraper<T, E> {
     crawling_concurrency: usize,
-    delay: Duration,
+    shutdown_timeout: Duration,
     context: ScraperContext,
     spider: Arc<dyn Spider<Item = T, Error = E>>,
 }
 
+/// What became of a request handed to the scraper, reported back to the crawl
+/// loop.
+pub enum ScrapeOutcome {
+    /// The request was scraped, successfully or not, and led to
+    /// `new_requests`.
+    Visited {
+        request: Request,
+
+        /// The URL relative URLs in `new_requests` resolve against.
+        base_url: String,
+
+        depth: usize,
+        new_requests: Vec<Request>,
+    },
+
+    /// The scrape failed and should be attempted again after a backoff.
+    Retry(QueuedRequest),
+
+    /// The request was handed back unscraped because the crawl is stopping.
+    Skipped,
+}
+
 pub struct SpiderScraper<T, E> {
+    pub downloader: Arc<dyn Downloader>,
     pub spider: Arc<dyn Spider<Item = T, Error = E>>,
     pub items_tx: mpsc::Sender<T>,
-    pub new_urls_tx: mpsc::Sender<(String, Vec<String>)>,
+    pub outcomes_tx: mpsc::Sender<ScrapeOutcome>,
 }
 
 impl<T, E> Scraper<T, E>
@@ -34,46 +62,47 @@ where
     E: Display + Send + 'static,
 {
     pub fn new(
-        active_spiders: Arc<AtomicUsize>,
-        barrier: Arc<Barrier>,
         crawling_concurrency: usize,
-        delay: Duration,
+        shutdown_timeout: Duration,
         spider: Arc<dyn Spider<Item = T, Error = E>>,
+        context: ScraperContext,
     ) -> Self {
         Self {
             crawling_concurrency,
-            delay,
-            context: ScraperContext {
-                active_spiders,
-                barrier,
-            },
+            shutdown_timeout,
+            context,
             spider,
         }
     }
 
-    pub fn scrape_urls(
+    pub fn scrape_requests(
         &self,
-        urls_to_visit: mpsc::Receiver<String>,
-        new_urls_tx: mpsc::Sender<(String, Vec<String>)>,
+        requests_to_visit: mpsc::Receiver<QueuedRequest>,
+        outcomes_tx: mpsc::Sender<ScrapeOutcome>,
         items_tx: mpsc::Sender<T>,
     ) {
         let url_processor = UrlProcessor::new(
-            self.context.active_spiders.clone(),
             self.crawling_concurrency,
-            self.delay,
+            self.shutdown_timeout,
+            self.context.handle.clone(),
+            self.context.limits,
+            self.context.retry,
+            self.context.slots.clone(),
+            self.context.stats.clone(),
         );
 
         let spider_scraper = SpiderScraper {
+            downloader: self.context.downloader.clone(),
             spider: self.spider.clone(),
             items_tx,
-            new_urls_tx,
+            outcomes_tx,
         };
 
         let barrier = self.context.barrier.clone();
 
         tokio::spawn(async move {
             url_processor
-                .process_urls(urls_to_visit, spider_scraper)
+                .process_requests(requests_to_visit, spider_scraper)
                 .await;
             barrier.wait().await;
         });
@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rand::Rng;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{self, Instant},
+};
+use url::Url;
+
+/// How URLs are grouped into download slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlotKey {
+    /// One slot per host name.
+    #[default]
+    Host,
+
+    /// One slot per resolved IP address, so hosts served by the same machine
+    /// share their politeness settings.
+    Ip,
+}
+
+/// The politeness settings of a download slot.
+#[derive(Debug, Clone)]
+pub struct DownloadSlot {
+    concurrency: usize,
+    delay: Duration,
+    jitter: Duration,
+}
+
+impl Default for DownloadSlot {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            delay: Duration::from_millis(250),
+            jitter: Duration::ZERO,
+        }
+    }
+}
+
+impl DownloadSlot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many URLs of the slot may be scraped at the same time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the minimum time between two scrapes starting in the slot.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Adds a random extra delay of up to `jitter` to every scrape, so the
+    /// slot's requests don't arrive on a fixed beat.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+}
+
+/// The slot configuration of a crawler.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Politeness {
+    pub key: SlotKey,
+    pub default: DownloadSlot,
+
+    /// Per-domain overrides of `default`, also applying to subdomains.
+    pub domains: HashMap<String, DownloadSlot>,
+}
+
+/// The download slots of a running crawl.
+pub(crate) struct Slots {
+    politeness: Politeness,
+    slots: Mutex<HashMap<String, Arc<Slot>>>,
+    resolved: Mutex<HashMap<String, String>>,
+    crawl_delays: Mutex<HashMap<String, Duration>>,
+}
+
+struct Slot {
+    semaphore: Arc<Semaphore>,
+    delay: Mutex<Duration>,
+    jitter: Duration,
+    next_start: Mutex<Instant>,
+}
+
+impl Slot {
+    fn new(settings: &DownloadSlot, crawl_delay: Option<Duration>) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(settings.concurrency)),
+            delay: Mutex::new(settings.delay.max(crawl_delay.unwrap_or_default())),
+            jitter: settings.jitter,
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves the earliest start time at least one delay after the
+    /// previous reservation.
+    fn reserve_start(&self) -> Instant {
+        let mut delay = *self.delay.lock().unwrap();
+        if !self.jitter.is_zero() {
+            delay += rand::thread_rng().gen_range(Duration::ZERO..=self.jitter);
+        }
+
+        let mut next_start = self.next_start.lock().unwrap();
+        let start = (*next_start).max(Instant::now());
+        *next_start = start + delay;
+        start
+    }
+}
+
+impl Slots {
+    pub fn new(politeness: Politeness) -> Self {
+        Self {
+            politeness,
+            slots: Mutex::new(HashMap::new()),
+            resolved: Mutex::new(HashMap::new()),
+            crawl_delays: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Raises the delay of `host`'s slot to at least `crawl_delay`, whether
+    /// the slot already exists, such as after downloading the host's
+    /// robots.txt, or not.
+    pub fn crawl_delay(&self, host: &str, crawl_delay: Duration) {
+        self.crawl_delays
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), crawl_delay);
+
+        let key = match self.politeness.key {
+            SlotKey::Ip => self.resolved.lock().unwrap().get(host).cloned(),
+            SlotKey::Host => None,
+        };
+        let key = key.as_deref().unwrap_or(host);
+        if let Some(slot) = self.slots.lock().unwrap().get(key) {
+            let mut delay = slot.delay.lock().unwrap();
+            *delay = (*delay).max(crawl_delay);
+        }
+    }
+
+    /// Waits until `url` may be scraped according to its slot. The returned
+    /// permit holds one of the slot's concurrency units until it is dropped.
+    pub async fn acquire(&self, url: &str) -> OwnedSemaphorePermit {
+        let slot = self.slot(url).await;
+        let permit = slot
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("slot semaphores are never closed");
+
+        let start = slot.reserve_start();
+        if start > Instant::now() {
+            time::sleep_until(start).await;
+        }
+        permit
+    }
+
+    async fn slot(&self, url: &str) -> Arc<Slot> {
+        let parsed = Url::parse(url).ok();
+        let host = parsed
+            .as_ref()
+            .and_then(Url::host_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let key = match (self.politeness.key, &parsed) {
+            (SlotKey::Ip, Some(url)) => self.resolve(&host, url).await,
+            _ => host.clone(),
+        };
+
+        let crawl_delay = self.crawl_delays.lock().unwrap().get(&host).copied();
+        let mut slots = self.slots.lock().unwrap();
+        slots
+            .entry(key)
+            .or_insert_with(|| Arc::new(Slot::new(self.settings(&host), crawl_delay)))
+            .clone()
+    }
+
+    /// Returns the settings of the most specific domain matching `host`.
+    fn settings(&self, host: &str) -> &DownloadSlot {
+        let mut domain = host;
+        loop {
+            if let Some(settings) = self.politeness.domains.get(domain) {
+                return settings;
+            }
+
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => return &self.politeness.default,
+            }
+        }
+    }
+
+    /// Resolves `host` to an IP address, falling back to the host name if it
+    /// cannot be resolved.
+    async fn resolve(&self, host: &str, url: &Url) -> String {
+        if let Some(ip) = self.resolved.lock().unwrap().get(host) {
+            return ip.clone();
+        }
+
+        let port = url.port_or_known_default().unwrap_or(80);
+        let ip = match tokio::net::lookup_host((host, port)).await {
+            Ok(mut addrs) => addrs.next().map(|addr| addr.ip().to_string()),
+            Err(err) => {
+                log::warn!("cannot resolve {}: {}", host, err);
+                None
+            }
+        };
+
+        let ip = ip.unwrap_or_else(|| host.to_string());
+        self.resolved
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), ip.clone());
+        ip
+    }
+}
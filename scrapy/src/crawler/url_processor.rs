@@ -1,69 +1,219 @@
 use std::{
-    fmt::Display,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
+    fmt::{self, Display},
+    sync::Arc,
     time::Duration,
 };
 
 use futures::StreamExt;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore, SemaphorePermit};
 
-use super::scraper::SpiderScraper;
+use crate::{Error, QueuedRequest};
+
+use super::{
+    handle::CrawlHandle,
+    limits::CrawlLimits,
+    report::{CloseReason, CrawlStats},
+    retry::RetryPolicy,
+    scraper::{ScrapeOutcome, SpiderScraper},
+    slots::Slots,
+};
+
+/// Why a request could not be scraped.
+enum Failure<E> {
+    Download(Error),
+    Scrape(E),
+
+    /// The request named a callback the spider does not have.
+    Callback(String),
+}
+
+impl<E: Display> Display for Failure<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Download(err) => err.fmt(f),
+            Self::Scrape(err) => err.fmt(f),
+            Self::Callback(callback) => write!(f, "the spider has no callback `{}`", callback),
+        }
+    }
+}
+
+impl<E> Failure<E> {
+    /// The type of the failure, as counted in the crawl stats.
+    fn kind(&self) -> String {
+        match self {
+            Self::Download(err) => format!("download/{}", err.kind()),
+            Self::Scrape(_) => String::from("scrape"),
+            Self::Callback(_) => String::from("callback"),
+        }
+    }
+}
 
 pub struct UrlProcessor {
-    active_spiders: Arc<AtomicUsize>,
-    crawling_concurrency: usize,
-    delay: Duration,
+    concurrency: Semaphore,
+    shutdown_timeout: Duration,
+    handle: CrawlHandle,
+    limits: CrawlLimits,
+    retry: Option<RetryPolicy>,
+    slots: Arc<Slots>,
+    stats: Arc<CrawlStats>,
 }
 
 impl UrlProcessor {
     pub fn new(
-        active_spiders: Arc<AtomicUsize>,
         crawling_concurrency: usize,
-        delay: Duration,
+        shutdown_timeout: Duration,
+        handle: CrawlHandle,
+        limits: CrawlLimits,
+        retry: Option<RetryPolicy>,
+        slots: Arc<Slots>,
+        stats: Arc<CrawlStats>,
     ) -> Self {
         Self {
-            active_spiders,
-            crawling_concurrency,
-            delay,
+            concurrency: Semaphore::new(crawling_concurrency),
+            shutdown_timeout,
+            handle,
+            limits,
+            retry,
+            slots,
+            stats,
         }
     }
 
-    pub async fn process_urls<T, E>(
+    /// Waits for both a unit of the URL's slot and of the crawl-wide
+    /// concurrency. The slot comes first, so URLs waiting on a busy or slow
+    /// host don't keep other hosts from being scraped.
+    async fn acquire(&self, url: &str) -> (OwnedSemaphorePermit, SemaphorePermit<'_>) {
+        let slot = self.slots.acquire(url).await;
+        let global = self
+            .concurrency
+            .acquire()
+            .await
+            .expect("the concurrency semaphore is never closed");
+
+        (slot, global)
+    }
+
+    pub async fn process_requests<T, E>(
         &self,
-        urls_to_visit: mpsc::Receiver<String>,
+        requests_to_visit: mpsc::Receiver<QueuedRequest>,
         spider_scraper: SpiderScraper<T, E>,
     ) where
         T: Send + 'static,
         E: Display + Send + 'static,
     {
-        tokio_stream::wrappers::ReceiverStream::new(urls_to_visit)
-            .for_each_concurrent(self.crawling_concurrency, |queued_url| {
-                let queued_url = queued_url.clone();
-                let active_spiders = self.active_spiders.clone();
+        tokio_stream::wrappers::ReceiverStream::new(requests_to_visit)
+            .for_each_concurrent(None, |queued: QueuedRequest| {
                 let items_tx = spider_scraper.items_tx.clone();
-                let new_urls_tx = spider_scraper.new_urls_tx.clone();
+                let outcomes_tx = spider_scraper.outcomes_tx.clone();
+                let downloader = spider_scraper.downloader.clone();
                 let spider = spider_scraper.spider.clone();
+                let handle = self.handle.clone();
+                let stats = self.stats.clone();
                 async move {
-                    active_spiders.fetch_add(1, Ordering::SeqCst);
-                    let mut urls = Vec::new();
-                    let res = spider.scrape(&queued_url.clone()).await.map_err(|err| {
-                        log::error!("{}", err);
-                        err
-                    });
-
-                    if let Ok((items, new_urls)) = res {
-                        for item in items {
-                            let _ = items_tx.send(item).await;
+                    // Requests waiting for their turn wait out a pause, and are
+                    // handed back without being scraped after a stop.
+                    let permits = loop {
+                        handle.unpaused().await;
+                        let permits = tokio::select! {
+                            permits = self.acquire(&queued.request.url) => permits,
+                            _ = handle.stopped() => break None,
+                        };
+
+                        if !handle.is_paused() {
+                            break Some(permits);
+                        }
+                    };
+
+                    let Some((slot, _global)) = permits.filter(|_| !handle.is_stopped()) else {
+                        let _ = outcomes_tx.send(ScrapeOutcome::Skipped).await;
+                        return;
+                    };
+
+                    // Like `max_pages`, pages are only counted on their first
+                    // attempt.
+                    if queued.attempts == 0 {
+                        stats.page_visited();
+                    }
+                    stats.request_sent();
+                    let mut base_url = queued.request.url.clone();
+                    let mut new_requests = Vec::new();
+                    let scrape = async {
+                        let response = downloader.fetch(&queued.request).await;
+                        // The slot only limits downloads, so the host is free
+                        // for the next one while this page is parsed.
+                        drop(slot);
+
+                        let response = response.map_err(Failure::Download)?;
+                        stats.response_downloaded(&response);
+                        let scraped = match &queued.request.callback {
+                            Some(callback) => spider
+                                .parse(callback, &response)
+                                .await
+                                .ok_or_else(|| Failure::Callback(callback.clone()))?,
+                            None => spider.scrape(&response).await,
+                        };
+                        let scraped = scraped.map_err(Failure::Scrape)?;
+                        Ok::<_, Failure<E>>((response.url, scraped))
+                    };
+                    let res = tokio::select! {
+                        res = scrape => Some(res),
+                        _ = async {
+                            handle.stopped().await;
+                            tokio::time::sleep(self.shutdown_timeout).await;
+                        } => None,
+                    };
+
+                    match res {
+                        Some(Ok((url, (items, requests)))) => {
+                            stats.items_scraped(items.len());
+                            for item in items {
+                                let _ = items_tx.send(item).await;
+                            }
+                            base_url = url;
+                            new_requests = requests;
+                        }
+                        Some(Err(err)) => {
+                            log::error!("{}", err);
+                            stats.scrape_failed(&err.kind());
+                            if self.limits.errors_reached(stats.errors()) {
+                                handle.close(CloseReason::MaxErrors);
+                            }
+
+                            let attempts = queued.attempts + 1;
+                            let retry =
+                                self.retry.is_some_and(|retry| retry.allows_retry(attempts));
+                            let retryable = match &err {
+                                Failure::Download(err) => {
+                                    self.retry.is_some_and(|retry| retry.is_retryable(err))
+                                }
+                                Failure::Scrape(err) => spider.is_retryable(err),
+                                Failure::Callback(_) => false,
+                            };
+                            if retry && retryable {
+                                let queued = QueuedRequest { attempts, ..queued };
+                                let _ = outcomes_tx.send(ScrapeOutcome::Retry(queued)).await;
+                                return;
+                            }
+
+                            stats.gave_up(&queued.request.url, attempts, err.to_string());
+                        }
+                        None => {
+                            log::warn!(
+                                "abandoning {} after the shutdown timeout",
+                                queued.request.url
+                            );
+                            let _ = outcomes_tx.send(ScrapeOutcome::Skipped).await;
+                            return;
                         }
-                        urls = new_urls;
                     }
 
-                    let _ = new_urls_tx.send((queued_url, urls)).await;
-                    tokio::time::sleep(self.delay).await;
-                    active_spiders.fetch_sub(1, Ordering::SeqCst);
+                    let outcome = ScrapeOutcome::Visited {
+                        request: queued.request,
+                        base_url,
+                        depth: queued.depth,
+                        new_requests,
+                    };
+                    let _ = outcomes_tx.send(outcome).await;
                 }
             })
             .await;
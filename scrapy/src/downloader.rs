@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::{Downloader, Error, Request, Response};
+
+/// The default [`Downloader`], which fetches requests over HTTP with a shared
+/// [`reqwest::Client`].
+///
+/// The default client follows redirects, keeps cookies across requests and
+/// gives up on a request after 30 seconds. A client configured differently
+/// can be passed to [`HttpDownloader::from_client`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use scrapy::{CrawlerBuilder, HttpDownloader};
+///
+/// let client = reqwest::Client::builder()
+///     .user_agent("mybot/1.0")
+///     .timeout(Duration::from_secs(10))
+///     .build()
+///     .unwrap();
+///
+/// let crawler = CrawlerBuilder::new()
+///     .downloader(HttpDownloader::from_client(client))
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpDownloader {
+    client: reqwest::Client,
+}
+
+impl Default for HttpDownloader {
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .cookie_store(true)
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+
+        Self { client }
+    }
+}
+
+impl HttpDownloader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a downloader sending every request through `client`.
+    pub fn from_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Downloader for HttpDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let started_at = Instant::now();
+
+        let mut builder = self.client.request(request.method.clone(), &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if !request.body.is_empty() {
+            builder = builder.body(request.body.clone());
+        }
+
+        let response = builder.send().await?;
+        let url = response.url().to_string();
+        let status = response.status();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.to_string(), value)
+            })
+            .collect();
+        let body = response.bytes().await?.to_vec();
+
+        Ok(Response {
+            url,
+            status,
+            headers,
+            body,
+            elapsed: started_at.elapsed(),
+            request: request.clone(),
+        })
+    }
+}
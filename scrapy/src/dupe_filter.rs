@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use url::Url;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{urls::canonicalize, DupeFilter, Request};
+
+/// Computes a 128-bit fingerprint identifying a request.
+///
+/// The URL is canonicalized first, so URLs that only differ in the order of
+/// their query parameters, their fragment, the case of their scheme and host,
+/// or an explicit default port share a fingerprint.
+pub fn request_fingerprint(method: &str, url: &str, body: &[u8]) -> u128 {
+    let mut hasher = Xxh3::new();
+    hasher.update(method.to_ascii_uppercase().as_bytes());
+    hasher.update(b"\0");
+    hasher.update(canonical_form(url, &[]).as_bytes());
+    hasher.update(b"\0");
+    hasher.update(body);
+    hasher.digest128()
+}
+
+fn canonical_form(url: &str, ignored_params: &[String]) -> String {
+    match Url::parse(url) {
+        Ok(url) => canonicalize(url, ignored_params).into(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// The default [`DupeFilter`], which remembers the fingerprints of the
+/// requests it has seen rather than the requests themselves.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::{DupeFilter, FingerprintDupeFilter, Method, Request};
+///
+/// let mut filter = FingerprintDupeFilter::new().ignore_param("sessionid");
+///
+/// assert!(!filter.request_seen(&"http://a.com/x?b=1&a=2&sessionid=1".into()));
+/// assert!(filter.request_seen(&"http://A.com:80/x?a=2&b=1&sessionid=2#frag".into()));
+/// assert!(!filter.request_seen(&Request::new("http://a.com/x?a=2&b=1").method(Method::POST)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FingerprintDupeFilter {
+    fingerprints: HashSet<u128>,
+    ignored_params: Vec<String>,
+}
+
+impl FingerprintDupeFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignores a query parameter, such as a session id, when fingerprinting URLs.
+    pub fn ignore_param<S>(mut self, param: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.ignored_params.push(param.into());
+        self
+    }
+
+    fn fingerprint(&self, request: &Request) -> u128 {
+        if self.ignored_params.is_empty() {
+            return request.fingerprint();
+        }
+
+        request_fingerprint(
+            request.method.as_str(),
+            &canonical_form(&request.url, &self.ignored_params),
+            &request.body,
+        )
+    }
+}
+
+impl DupeFilter for FingerprintDupeFilter {
+    fn request_seen(&mut self, request: &Request) -> bool {
+        !self.fingerprints.insert(self.fingerprint(request))
+    }
+}
@@ -0,0 +1,83 @@
+/// Errors produced by the `scrapy` crate.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A failure reported by a custom [`Downloader`](crate::Downloader) or
+    /// [`DownloaderMiddleware`](crate::DownloaderMiddleware).
+    #[error("Download failed: {0}")]
+    Download(Box<dyn std::error::Error + Send + Sync>),
+
+    /// A failure reported by an [`ItemPipeline`](crate::ItemPipeline) while
+    /// opening or closing.
+    #[error("Item pipeline failed: {0}")]
+    Pipeline(Box<dyn std::error::Error + Send + Sync>),
+
+    /// An [`ItemPipeline`](crate::ItemPipeline) registered on the crawler
+    /// takes another type of items than the spider crawled.
+    #[error("An item pipeline for `{pipeline}` items cannot process the spider's `{item}` items")]
+    PipelineItemType {
+        pipeline: &'static str,
+        item: &'static str,
+    },
+
+    #[error("Invalid selector: {0}")]
+    Selector(#[from] SelectorError),
+
+    /// A request missing from an offline [`HttpCache`](crate::HttpCache).
+    #[error("Not in the HTTP cache: {0}")]
+    CacheMiss(String),
+}
+
+impl Error {
+    /// A short name for the kind of error, used as a stats key.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::Url(_) => "url",
+            Self::Http(_) => "http",
+            Self::Json(_) => "json",
+            Self::Io(_) => "io",
+            Self::Download(_) => "download",
+            Self::Pipeline(_) => "pipeline",
+            Self::PipelineItemType { .. } => "pipeline_item_type",
+            Self::Selector(_) => "selector",
+            Self::CacheMiss(_) => "cache_miss",
+        }
+    }
+}
+
+/// Why a [`Document`](crate::Document) query could not run.
+#[derive(thiserror::Error, Debug)]
+pub enum SelectorError {
+    #[error("CSS selector `{query}` is invalid: {message}")]
+    Css { query: String, message: String },
+
+    #[error("XPath expression `{query}` failed: {message}")]
+    XPath { query: String, message: String },
+
+    #[error("Invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+}
+
+/// Why an [`ItemPipeline`](crate::ItemPipeline) stage did not pass an item on.
+#[derive(thiserror::Error, Debug)]
+pub enum ItemError {
+    /// The item was dropped on purpose, such as a duplicate or an incomplete
+    /// item, and counts as dropped rather than as an error.
+    #[error("Item dropped: {0}")]
+    Dropped(String),
+
+    /// The item could not be processed.
+    #[error("Item failed: {0}")]
+    Failed(Box<dyn std::error::Error + Send + Sync>),
+}
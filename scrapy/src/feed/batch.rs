@@ -0,0 +1,93 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+/// A batch of a feed being written.
+///
+/// The batch is written to a `.part` file next to its path, and only renamed
+/// to its path once complete, so a file at that path is never partial.
+pub struct BatchFile {
+    path: PathBuf,
+    part_path: PathBuf,
+    out: Output,
+    written: u64,
+}
+
+enum Output {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl BatchFile {
+    /// Starts a batch that will end up at `path`, creating its directory if
+    /// needed.
+    pub fn create(path: PathBuf, gzip: bool) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let part_path = part_path(&path);
+        let file = BufWriter::new(File::create(&part_path)?);
+        let out = if gzip {
+            Output::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Output::Plain(file)
+        };
+
+        Ok(Self {
+            path,
+            part_path,
+            out,
+            written: 0,
+        })
+    }
+
+    /// The number of bytes written to the batch, before compression.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Completes the batch and moves it to its path, returning the path.
+    pub fn commit(self) -> io::Result<PathBuf> {
+        let file = match self.out {
+            Output::Plain(file) => file,
+            Output::Gzip(encoder) => encoder.finish()?,
+        };
+        let file = file.into_inner().map_err(|err| err.into_error())?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&self.part_path, &self.path)?;
+        Ok(self.path)
+    }
+}
+
+impl Write for BatchFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = match &mut self.out {
+            Output::Plain(file) => file.write(buf)?,
+            Output::Gzip(encoder) => encoder.write(buf)?,
+        };
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.out {
+            Output::Plain(file) => file.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// The path a batch is written to until it is complete.
+fn part_path(path: &Path) -> PathBuf {
+    let mut part_path = OsString::from(path.as_os_str());
+    part_path.push(".part");
+    PathBuf::from(part_path)
+}
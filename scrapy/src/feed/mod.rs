@@ -0,0 +1,277 @@
+use std::{
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::{Error, ItemError, ItemPipeline};
+
+use self::{batch::BatchFile, writer::FeedWriter};
+
+mod batch;
+mod writer;
+
+/// The formats items can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    /// One JSON object per line.
+    JsonLines,
+
+    /// A single JSON array holding every item.
+    Json,
+
+    /// One row per item, after a header row naming the fields.
+    Csv,
+
+    /// An `<items>` document with an `<item>` element per item.
+    Xml,
+}
+
+impl FeedFormat {
+    /// Infers the format from a file extension: `.jsonl`, `.jl` or `.ndjson`
+    /// for JSON Lines, `.json`, `.csv` and `.xml`, optionally followed by
+    /// `.gz`.
+    pub fn from_path<P>(path: P) -> Option<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut path = path.as_ref();
+        if path.extension()?.eq_ignore_ascii_case("gz") {
+            path = Path::new(path.file_stem()?);
+        }
+
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "jsonl" | "jl" | "ndjson" => Some(Self::JsonLines),
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "xml" => Some(Self::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// An [`ItemPipeline`] writing every item it gets to files, in one of the
+/// [`FeedFormat`]s.
+///
+/// Items are serialized with serde. The feed's path is a template, where
+/// these placeholders are replaced:
+///
+/// * `{spider}` - The name of the spider being crawled.
+/// * `{time}` - When the crawl started, in UTC, such as
+///   `2023-11-05T14-30-00`.
+/// * `{batch}` - The number of the batch, starting at 1.
+///
+/// By default the whole crawl goes to a single file. With
+/// [`batch_items`](FeedExporter::batch_items) or
+/// [`batch_size`](FeedExporter::batch_size), the feed is split into batches,
+/// each a complete file in the feed's format, and the path must contain
+/// `{batch}`.
+///
+/// Each file is written next to its path with a `.part` suffix, and renamed
+/// to its path once complete, so a file at the feed's path is never partial.
+/// Existing files are replaced. Items are passed on unchanged, so the
+/// exporter can be followed by other stages or exporters.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::{CrawlerBuilder, FeedExporter, FeedFormat};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Quote {
+///     text: String,
+///     author: String,
+/// }
+///
+/// let exporter = FeedExporter::<Quote>::new("out/{spider}/{time}-{batch}.csv.gz", FeedFormat::Csv)
+///     .fields(["author", "text"])
+///     .batch_items(10_000)
+///     .gzip(true);
+///
+/// let crawler = CrawlerBuilder::new().item_pipeline(exporter).build();
+/// ```
+pub struct FeedExporter<T> {
+    path: String,
+    format: FeedFormat,
+    fields: Option<Vec<String>>,
+    batch_items: Option<usize>,
+    batch_size: Option<u64>,
+    gzip: bool,
+    feed: Mutex<Option<Feed>>,
+    item: PhantomData<fn(T)>,
+}
+
+/// The state of a feed while a crawl is running.
+struct Feed {
+    spider: String,
+    time: String,
+    batch: usize,
+    writer: Option<FeedWriter<BatchFile>>,
+}
+
+impl<T> FeedExporter<T> {
+    /// Creates an exporter writing items to `path` in `format`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the feed, which may contain placeholders.
+    /// * `format` - The format items are written in.
+    pub fn new<P>(path: P, format: FeedFormat) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            path: path.as_ref().to_string_lossy().into_owned(),
+            format,
+            fields: None,
+            batch_items: None,
+            batch_size: None,
+            gzip: false,
+            feed: Mutex::new(None),
+            item: PhantomData,
+        }
+    }
+
+    /// Exports only `fields`, in that order. By default every field is
+    /// exported in the order the item serializes them.
+    ///
+    /// CSV feeds use the fields as their columns. Without them, the columns
+    /// are the fields of the first item of each batch.
+    pub fn fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Starts a new batch after every `batch_items` items.
+    pub fn batch_items<O>(mut self, batch_items: O) -> Self
+    where
+        O: Into<Option<usize>>,
+    {
+        self.batch_items = batch_items.into();
+        self
+    }
+
+    /// Starts a new batch once a batch holds `batch_size` bytes or more,
+    /// counted before compression.
+    pub fn batch_size<O>(mut self, batch_size: O) -> Self
+    where
+        O: Into<Option<u64>>,
+    {
+        self.batch_size = batch_size.into();
+        self
+    }
+
+    /// Compresses every file of the feed with gzip. The path should end with
+    /// `.gz` to match.
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    fn is_batched(&self) -> bool {
+        self.batch_items.is_some() || self.batch_size.is_some()
+    }
+
+    /// Whether `writer` holds a full batch.
+    fn is_full(&self, writer: &FeedWriter<BatchFile>) -> bool {
+        self.batch_items
+            .is_some_and(|items| writer.items() >= items)
+            || self
+                .batch_size
+                .is_some_and(|size| writer.get_ref().written() >= size)
+    }
+
+    /// Starts the next batch of `feed`.
+    fn start_batch(&self, feed: &mut Feed) -> Result<FeedWriter<BatchFile>, Error> {
+        feed.batch += 1;
+        let path = self
+            .path
+            .replace("{spider}", &feed.spider)
+            .replace("{time}", &feed.time)
+            .replace("{batch}", &feed.batch.to_string());
+
+        let file = BatchFile::create(PathBuf::from(path), self.gzip)?;
+        Ok(FeedWriter::new(self.format, file, self.fields.clone())?)
+    }
+}
+
+/// Completes a batch, moving it to its final path.
+fn commit(writer: FeedWriter<BatchFile>) -> Result<(), Error> {
+    let items = writer.items();
+    let path = writer.finish()?.commit()?;
+    log::info!("exported {} items to {}", items, path.display());
+    Ok(())
+}
+
+#[async_trait]
+impl<T> ItemPipeline<T> for FeedExporter<T>
+where
+    T: Serialize + Send + 'static,
+{
+    async fn open(&self, spider: &str) -> Result<(), Error> {
+        if self.is_batched() && !self.path.contains("{batch}") {
+            return Err(Error::Pipeline(
+                format!("batched feed {} has no {{batch}} in its path", self.path).into(),
+            ));
+        }
+
+        let mut feed = Feed {
+            spider: spider.to_string(),
+            time: Utc::now().format("%Y-%m-%dT%H-%M-%S").to_string(),
+            batch: 0,
+            writer: None,
+        };
+        // The first batch is started right away, so a crawl without items
+        // still leaves an empty feed behind.
+        feed.writer = Some(self.start_batch(&mut feed)?);
+        *self.feed.lock().unwrap() = Some(feed);
+        Ok(())
+    }
+
+    async fn process_item(&self, item: T) -> Result<T, ItemError> {
+        let value = serde_json::to_value(&item).map_err(|err| ItemError::Failed(Box::new(err)))?;
+
+        let mut feed = self.feed.lock().unwrap();
+        let Some(feed) = feed.as_mut() else {
+            return Err(ItemError::Failed("the feed is not open".into()));
+        };
+
+        let mut writer = match feed.writer.take() {
+            Some(writer) => writer,
+            None => self
+                .start_batch(feed)
+                .map_err(|err| ItemError::Failed(Box::new(err)))?,
+        };
+        writer
+            .write(value)
+            .map_err(|err| ItemError::Failed(Box::new(err)))?;
+
+        // Full batches are completed right away rather than when the next
+        // item comes, so they are available while the crawl goes on.
+        if self.is_full(&writer) {
+            commit(writer).map_err(|err| ItemError::Failed(Box::new(err)))?;
+        } else {
+            feed.writer = Some(writer);
+        }
+
+        Ok(item)
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        let feed = self.feed.lock().unwrap().take();
+        match feed.and_then(|feed| feed.writer) {
+            Some(writer) => commit(writer),
+            None => Ok(()),
+        }
+    }
+}
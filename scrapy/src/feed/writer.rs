@@ -0,0 +1,245 @@
+use std::io::{self, Write};
+
+use serde_json::{Map, Value};
+
+use super::FeedFormat;
+
+/// Writes items to `W` in one of the feed formats, taking care of the
+/// format's header and footer.
+pub struct FeedWriter<W> {
+    format: Format<W>,
+    fields: Option<Vec<String>>,
+    items: usize,
+}
+
+enum Format<W> {
+    JsonLines(W),
+    Json(W),
+    Csv {
+        out: W,
+        headers: Option<Vec<String>>,
+    },
+    Xml(W),
+}
+
+impl<W: Write> FeedWriter<W> {
+    /// Starts a feed on `out`, writing the format's header.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format of the feed.
+    /// * `out` - Where the feed is written.
+    /// * `fields` - The fields exported for each item, in order. All fields
+    ///   are exported in the order they are serialized if `None`.
+    pub fn new(format: FeedFormat, mut out: W, fields: Option<Vec<String>>) -> io::Result<Self> {
+        let format = match format {
+            FeedFormat::JsonLines => Format::JsonLines(out),
+            FeedFormat::Json => {
+                out.write_all(b"[")?;
+                Format::Json(out)
+            }
+            FeedFormat::Csv => {
+                if let Some(fields) = &fields {
+                    out.write_all(&csv_record(fields)?)?;
+                }
+
+                Format::Csv {
+                    out,
+                    headers: fields.clone(),
+                }
+            }
+            FeedFormat::Xml => {
+                out.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<items>\n")?;
+                Format::Xml(out)
+            }
+        };
+
+        Ok(Self {
+            format,
+            fields,
+            items: 0,
+        })
+    }
+
+    /// The number of items written so far.
+    pub fn items(&self) -> usize {
+        self.items
+    }
+
+    /// The output the feed is written to.
+    pub fn get_ref(&self) -> &W {
+        match &self.format {
+            Format::JsonLines(out) | Format::Json(out) | Format::Xml(out) => out,
+            Format::Csv { out, .. } => out,
+        }
+    }
+
+    /// Writes an item serialized to JSON.
+    pub fn write(&mut self, item: Value) -> io::Result<()> {
+        let item = select(item, self.fields.as_deref());
+
+        match &mut self.format {
+            Format::JsonLines(out) => {
+                serde_json::to_writer(&mut *out, &item)?;
+                out.write_all(b"\n")?;
+            }
+            Format::Json(out) => {
+                out.write_all(if self.items == 0 { b"\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut *out, &item)?;
+            }
+            Format::Csv { out, headers } => {
+                let Value::Object(item) = item else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "CSV feeds can only export items serialized as maps",
+                    ));
+                };
+
+                // Without configured fields, the first item decides the columns.
+                if headers.is_none() {
+                    let fields = item.keys().cloned().collect::<Vec<_>>();
+                    out.write_all(&csv_record(&fields)?)?;
+                    *headers = Some(fields);
+                }
+
+                let headers = headers.as_deref().unwrap_or_default();
+                let cells = headers.iter().map(|field| csv_cell(item.get(field)));
+                out.write_all(&csv_record(cells)?)?;
+            }
+            Format::Xml(out) => {
+                out.write_all(b"<item>")?;
+                write_xml(out, &item)?;
+                out.write_all(b"</item>\n")?;
+            }
+        }
+
+        self.items += 1;
+        Ok(())
+    }
+
+    /// Writes the format's footer and returns the output, flushed.
+    pub fn finish(self) -> io::Result<W> {
+        let mut out = match self.format {
+            Format::JsonLines(out) => out,
+            Format::Json(mut out) => {
+                out.write_all(if self.items == 0 { b"]\n" } else { b"\n]\n" })?;
+                out
+            }
+            Format::Csv { out, .. } => out,
+            Format::Xml(mut out) => {
+                out.write_all(b"</items>\n")?;
+                out
+            }
+        };
+
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+/// Keeps the exported `fields` of an item, in order. Fields the item lacks
+/// are exported as `null`.
+fn select(item: Value, fields: Option<&[String]>) -> Value {
+    match (item, fields) {
+        (Value::Object(mut item), Some(fields)) => Value::Object(
+            fields
+                .iter()
+                .map(|field| (field.clone(), item.remove(field).unwrap_or(Value::Null)))
+                .collect::<Map<_, _>>(),
+        ),
+        (item, _) => item,
+    }
+}
+
+/// Formats a CSV row. Each row is formatted on its own, so it reaches the
+/// output as soon as the item is written.
+fn csv_record<I>(cells: I) -> io::Result<Vec<u8>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut record = csv::Writer::from_writer(Vec::new());
+    record.write_record(cells)?;
+    record.into_inner().map_err(|err| err.into_error())
+}
+
+/// Formats a field as a CSV cell. Strings are written as is, missing and
+/// `null` fields are left empty, and anything else is written as JSON.
+fn csv_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Writes a value as XML. Map fields become elements named after the field,
+/// or `<field name="...">` elements if the field's name isn't a valid element
+/// name, and list entries become `<value>` elements.
+fn write_xml<W: Write>(out: &mut W, value: &Value) -> io::Result<()> {
+    match value {
+        Value::Null => Ok(()),
+        Value::String(value) => out.write_all(escape_xml(value).as_bytes()),
+        Value::Array(values) => {
+            for value in values {
+                out.write_all(b"<value>")?;
+                write_xml(out, value)?;
+                out.write_all(b"</value>")?;
+            }
+            Ok(())
+        }
+        Value::Object(fields) => {
+            for (field, value) in fields {
+                if is_xml_name(field) {
+                    write!(out, "<{}>", field)?;
+                    write_xml(out, value)?;
+                    write!(out, "</{}>", field)?;
+                } else {
+                    write!(out, "<field name=\"{}\">", escape_xml(field))?;
+                    write_xml(out, value)?;
+                    out.write_all(b"</field>")?;
+                }
+            }
+            Ok(())
+        }
+        value => write!(out, "{}", value),
+    }
+}
+
+/// Whether `name` matches the XML `Name` production, without colons, which
+/// would make it a name in an undeclared namespace.
+fn is_xml_name(name: &str) -> bool {
+    let is_start_char = |c: char| {
+        matches!(c,
+            'A'..='Z' | '_' | 'a'..='z'
+            | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+            | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+            | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+            | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}'
+        )
+    };
+    let is_char = |c: char| {
+        is_start_char(c)
+            || matches!(c,
+                '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}'
+            )
+    };
+
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_start_char) && chars.all(is_char)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,366 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{DownloaderMiddleware, Error, Request, Response};
+
+/// The request [`meta`](Request::meta) flagging responses served from the
+/// cache.
+const CACHED: &str = "cached";
+
+/// How an [`HttpCache`] decides whether a cached response can be used instead
+/// of downloading the request again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Cached responses are used forever, whatever their headers say.
+    Always,
+
+    /// Cached responses are used until they are older than the given age.
+    MaxAge(Duration),
+
+    /// Responses are cached and reused the way a private HTTP cache does,
+    /// following RFC 9111.
+    ///
+    /// Responses are fresh for their `Cache-Control: max-age`, or until
+    /// their `Expires` date, or, lacking both, for a tenth of the time since
+    /// their `Last-Modified` date. `no-store` responses are never cached, and
+    /// `no-cache` responses are always revalidated. Stale responses with an
+    /// `ETag` or `Last-Modified` header are revalidated with a conditional
+    /// request, and reused if the server answers `304 Not Modified`.
+    Rfc9111,
+}
+
+/// A [`DownloaderMiddleware`] caching responses on disk, so pages are
+/// downloaded once while a spider is being developed, and crawls can be
+/// replayed without a network.
+///
+/// Responses are stored under the cache's directory, keyed by the
+/// [`fingerprint`](Request::fingerprint) of their request. Server errors are
+/// never cached. Whether a cached response is used is up to the cache's
+/// [`CachePolicy`], [`Always`](CachePolicy::Always) by default. Responses
+/// served from the cache have the `cached` [`meta`](Request::meta) of their
+/// request set to `true`.
+///
+/// In offline mode, every request is answered from the cache, however old
+/// its response, and requests missing from it fail right away with
+/// [`Error::CacheMiss`], without being retried.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use scrapy::{CachePolicy, CrawlerBuilder, HttpCache};
+///
+/// let day = Duration::from_secs(24 * 3600);
+/// let cache = HttpCache::new(".cache/http").policy(CachePolicy::MaxAge(day));
+///
+/// let crawler = CrawlerBuilder::new().downloader_middleware(cache).build();
+/// ```
+pub struct HttpCache {
+    dir: PathBuf,
+    policy: CachePolicy,
+    offline: bool,
+}
+
+/// What is stored of a response, next to its body.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+
+    /// When the response was stored, in seconds since the Unix epoch.
+    stored_at: u64,
+}
+
+impl HttpCache {
+    /// Creates a cache storing responses in `dir`, which is created if
+    /// needed.
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            policy: CachePolicy::Always,
+            offline: false,
+        }
+    }
+
+    /// Sets how the cache decides whether a cached response can be used.
+    pub fn policy(mut self, policy: CachePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets whether requests are only ever answered from the cache.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// The paths of the metadata and the body of a request's cached response.
+    fn paths(&self, request: &Request) -> (PathBuf, PathBuf) {
+        let fingerprint = format!("{:032x}", request.fingerprint());
+        let dir = self.dir.join(&fingerprint[..2]);
+        (
+            dir.join(format!("{}.json", fingerprint)),
+            dir.join(format!("{}.body", fingerprint)),
+        )
+    }
+
+    /// Loads the cached response of `request`, if any.
+    fn load(&self, request: &Request) -> Option<(Entry, Vec<u8>)> {
+        let (entry_path, body_path) = self.paths(request);
+
+        let entry = match fs::read(&entry_path) {
+            Ok(entry) => entry,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                log::warn!("cannot read {}: {}", entry_path.display(), err);
+                return None;
+            }
+        };
+
+        let loaded = serde_json::from_slice::<Entry>(&entry)
+            .map_err(io::Error::from)
+            .and_then(|entry| Ok((entry, fs::read(&body_path)?)));
+        match loaded {
+            Ok(loaded) => Some(loaded),
+            Err(err) => {
+                log::warn!("ignoring cached {}: {}", request.url, err);
+                None
+            }
+        }
+    }
+
+    /// Stores a response. The body is written before the metadata, so an
+    /// interrupted write leaves no entry behind.
+    fn store(&self, entry: &Entry, body: Option<&[u8]>, request: &Request) -> io::Result<()> {
+        let (entry_path, body_path) = self.paths(request);
+        if let Some(parent) = entry_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if let Some(body) = body {
+            write_atomic(&body_path, body)?;
+        }
+        write_atomic(&entry_path, &serde_json::to_vec(entry)?)
+    }
+
+    fn is_storable(&self, response: &Response) -> bool {
+        if response.status.is_server_error() {
+            return false;
+        }
+
+        match self.policy {
+            CachePolicy::Always | CachePolicy::MaxAge(_) => true,
+            CachePolicy::Rfc9111 => {
+                response.status != StatusCode::PARTIAL_CONTENT
+                    && !has_directive(
+                        header(&response.request.headers, "cache-control"),
+                        "no-store",
+                    )
+                    && !has_directive(response.header("cache-control"), "no-store")
+            }
+        }
+    }
+
+    /// Whether a cached response can be used as is for `request`.
+    fn is_fresh(&self, entry: &Entry, request: &Request) -> bool {
+        let age = now().saturating_sub(entry.stored_at);
+
+        match self.policy {
+            CachePolicy::Always => true,
+            CachePolicy::MaxAge(max_age) => age < max_age.as_secs(),
+            CachePolicy::Rfc9111 => {
+                let request_cache_control = header(&request.headers, "cache-control");
+                let cache_control = entry.header("cache-control");
+                if has_directive(request_cache_control, "no-cache")
+                    || directive(request_cache_control, "max-age") == Some("0")
+                    || has_directive(cache_control, "no-cache")
+                {
+                    return false;
+                }
+
+                // The age the response already had when it was stored counts.
+                let initial_age = entry
+                    .header("age")
+                    .and_then(|age| age.trim().parse::<u64>().ok())
+                    .unwrap_or(0);
+                entry.freshness_lifetime() > initial_age + age
+            }
+        }
+    }
+}
+
+impl Entry {
+    fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+
+    /// How long the response is fresh for, in seconds, as RFC 9111 defines
+    /// it for private caches.
+    fn freshness_lifetime(&self) -> u64 {
+        if let Some(max_age) = directive(self.header("cache-control"), "max-age") {
+            return max_age.parse().unwrap_or(0);
+        }
+
+        let date = self.date("date").unwrap_or(self.stored_at as i64);
+        if self.header("expires").is_some() {
+            // Invalid dates, such as `0`, mean the response already expired.
+            return self
+                .date("expires")
+                .map_or(0, |expires| expires.saturating_sub(date).max(0) as u64);
+        }
+
+        // Lacking explicit freshness, responses modified long ago are
+        // guessed to stay unchanged for a while.
+        match self.date("last-modified") {
+            Some(last_modified) => (date.saturating_sub(last_modified).max(0) / 10) as u64,
+            None => 0,
+        }
+    }
+
+    /// The Unix timestamp of a date header.
+    fn date(&self, name: &str) -> Option<i64> {
+        let date = DateTime::parse_from_rfc2822(self.header(name)?.trim()).ok()?;
+        Some(date.timestamp())
+    }
+
+    /// Builds the cached response, flagged so it isn't stored again on its
+    /// way back through the cache.
+    fn into_response(self, body: Vec<u8>, request: &Request) -> Response {
+        Response {
+            url: self.url,
+            status: StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK),
+            headers: self.headers,
+            body,
+            elapsed: Duration::ZERO,
+            request: request.clone().meta(CACHED, true),
+        }
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for HttpCache {
+    async fn process_request(&self, request: &mut Request) -> Result<Option<Response>, Error> {
+        let Some((entry, body)) = self.load(request) else {
+            if self.offline {
+                return Err(Error::CacheMiss(request.url.clone()));
+            }
+            return Ok(None);
+        };
+
+        if self.offline || self.is_fresh(&entry, request) {
+            return Ok(Some(entry.into_response(body, request)));
+        }
+
+        if self.policy == CachePolicy::Rfc9111 {
+            if let Some(etag) = entry.header("etag") {
+                request
+                    .headers
+                    .push(("If-None-Match".into(), etag.to_string()));
+            }
+            if let Some(last_modified) = entry.header("last-modified") {
+                request
+                    .headers
+                    .push(("If-Modified-Since".into(), last_modified.to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn process_response(&self, response: Response) -> Result<Response, Error> {
+        if is_cached(&response) {
+            return Ok(response);
+        }
+
+        // A revalidated response is used again, with its headers updated.
+        if response.status == StatusCode::NOT_MODIFIED && self.policy == CachePolicy::Rfc9111 {
+            if let Some((mut entry, body)) = self.load(&response.request) {
+                for (name, value) in response.headers {
+                    entry
+                        .headers
+                        .retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+                    entry.headers.push((name, value));
+                }
+                entry.stored_at = now();
+
+                if let Err(err) = self.store(&entry, None, &response.request) {
+                    log::warn!("cannot cache {}: {}", response.url, err);
+                }
+                return Ok(entry.into_response(body, &response.request));
+            }
+        }
+
+        if self.is_storable(&response) {
+            let entry = Entry {
+                url: response.url.clone(),
+                status: response.status.as_u16(),
+                headers: response.headers.clone(),
+                stored_at: now(),
+            };
+            if let Err(err) = self.store(&entry, Some(&response.body), &response.request) {
+                log::warn!("cannot cache {}: {}", response.url, err);
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+/// Whether a response was served from the cache.
+fn is_cached(response: &Response) -> bool {
+    response.request.meta.get(CACHED) == Some(&true.into())
+}
+
+/// The first value of the header `name`, compared ignoring case.
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Finds a `Cache-Control` directive, returning its value, or an empty string
+/// if it has none.
+fn directive<'a>(cache_control: Option<&'a str>, name: &str) -> Option<&'a str> {
+    cache_control?.split(',').find_map(|directive| {
+        let (directive, value) = directive.split_once('=').unwrap_or((directive, ""));
+        directive
+            .trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+fn has_directive(cache_control: Option<&str>, name: &str) -> bool {
+    directive(cache_control, name).is_some()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Writes a file through a temporary file, so it is never seen half written.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut part_path = OsString::from(path.as_os_str());
+    part_path.push(".part");
+
+    fs::write(&part_path, contents)?;
+    fs::rename(&part_path, path)
+}
@@ -1,5 +1,56 @@
 mod traits;
-pub use traits::{FromHTML, Spider};
+pub use traits::{
+    Downloader, DownloaderMiddleware, DupeFilter, FromHTML, ItemPipeline, Scheduler, Spider,
+};
+
+mod crawl_spider;
+pub use crawl_spider::{CrawlSpider, Rule};
 
 mod crawler;
-pub use crawler::{Crawler, CrawlerBuilder};
+pub use crawler::{
+    CloseReason, CrawlHandle, CrawlReport, Crawler, CrawlerBuilder, DownloadSlot, RetryPolicy,
+    ScrapeFailure, SlotKey,
+};
+
+mod downloader;
+pub use downloader::HttpDownloader;
+
+mod dupe_filter;
+pub use dupe_filter::{request_fingerprint, FingerprintDupeFilter};
+
+mod error;
+pub use error::{Error, ItemError, SelectorError};
+
+mod feed;
+pub use feed::{FeedExporter, FeedFormat};
+
+mod http_cache;
+pub use http_cache::{CachePolicy, HttpCache};
+
+mod link_extractor;
+pub use link_extractor::LinkExtractor;
+
+mod request;
+pub use request::Request;
+pub use reqwest::{Method, StatusCode};
+
+mod response;
+pub use response::Response;
+
+mod robots;
+pub use robots::RobotsTxt;
+
+mod scheduler;
+pub use scheduler::{CrawlOrder, PriorityScheduler, QueuedRequest};
+
+mod selector;
+pub use selector::{Document, Selection};
+
+mod sitemap_spider;
+pub use sitemap_spider::SitemapSpider;
+
+mod stats;
+pub use stats::{Stats, StatsCollector};
+
+mod urls;
+pub use urls::{base_url, canonicalize_url, urljoin};
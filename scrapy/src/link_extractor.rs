@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+use url::Url;
+
+use crate::{canonicalize_url, urljoin, Document, Request, Response, SelectorError};
+
+/// Extracts the links of a page worth following, as requests ready to be
+/// returned from [`Spider::scrape`](crate::Spider::scrape).
+///
+/// Links are taken from `<a>` and `<area>` elements, and optionally from
+/// `<link>` and `<iframe>` elements. They are resolved against the page's URL
+/// or its `<base href>`, stripped of their fragment and deduplicated. Links marked
+/// `rel="nofollow"` and links that are not HTTP(S) URLs, such as `mailto:`
+/// links, are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::LinkExtractor;
+///
+/// let pages = LinkExtractor::new()
+///     .restrict_css("ul.pager")
+///     .allow(r"/page/\d+/")?
+///     .allow_domains(["quotes.toscrape.com"]);
+/// # Ok::<(), scrapy::SelectorError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LinkExtractor {
+    allow: Vec<Regex>,
+    deny: Vec<Regex>,
+    allow_domains: Vec<String>,
+    restrict_css: Vec<String>,
+    link_tags: bool,
+    iframes: bool,
+}
+
+impl LinkExtractor {
+    /// Creates an extractor following every `<a>` and `<area>` link.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only follows links whose URL matches the regular expression `pattern`,
+    /// or one of the other allowed patterns. Every link is allowed if no
+    /// pattern is.
+    ///
+    /// # Returns
+    ///
+    /// The extractor, or an error if `pattern` is not a valid regular
+    /// expression.
+    pub fn allow<S>(mut self, pattern: S) -> Result<Self, SelectorError>
+    where
+        S: AsRef<str>,
+    {
+        self.allow.push(Regex::new(pattern.as_ref())?);
+        Ok(self)
+    }
+
+    /// Skips links whose URL matches the regular expression `pattern`, even
+    /// if they are allowed.
+    ///
+    /// # Returns
+    ///
+    /// The extractor, or an error if `pattern` is not a valid regular
+    /// expression.
+    pub fn deny<S>(mut self, pattern: S) -> Result<Self, SelectorError>
+    where
+        S: AsRef<str>,
+    {
+        self.deny.push(Regex::new(pattern.as_ref())?);
+        Ok(self)
+    }
+
+    /// Only follows links to `domains` and their subdomains. Every domain is
+    /// allowed by default.
+    pub fn allow_domains<I, S>(mut self, domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let domains = domains
+            .into_iter()
+            .map(|domain| domain.into().to_lowercase());
+        self.allow_domains.extend(domains);
+        self
+    }
+
+    /// Only follows links inside the elements matching the CSS selector
+    /// `selector`, or one of the other restricting selectors, including the
+    /// matching elements that are links themselves. Links are taken from the
+    /// whole page by default.
+    pub fn restrict_css<S>(mut self, selector: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.restrict_css.push(selector.into());
+        self
+    }
+
+    /// Sets whether `<link href>` elements, such as feeds and alternate
+    /// languages, are followed.
+    pub fn link_tags(mut self, link_tags: bool) -> Self {
+        self.link_tags = link_tags;
+        self
+    }
+
+    /// Sets whether the pages embedded by `<iframe src>` elements are
+    /// followed.
+    pub fn iframes(mut self, iframes: bool) -> Self {
+        self.iframes = iframes;
+        self
+    }
+
+    /// Extracts the links of a downloaded page.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The page to extract links from.
+    ///
+    /// # Returns
+    ///
+    /// A `GET` request for each link, in the order they appear on the page, or
+    /// an error if a selector is invalid.
+    pub fn extract(&self, response: &Response) -> Result<Vec<Request>, SelectorError> {
+        self.extract_from(&response.document(), &response.url)
+    }
+
+    /// Extracts the links of an already parsed page, see
+    /// [`extract`](LinkExtractor::extract).
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The page to extract links from.
+    /// * `page_url` - The URL the page was downloaded from.
+    pub fn extract_from(
+        &self,
+        document: &Document,
+        page_url: &str,
+    ) -> Result<Vec<Request>, SelectorError> {
+        let base = match document.css("base[href]")?.attr("href") {
+            Some(href) => urljoin(page_url, &href).unwrap_or_else(|_| page_url.to_string()),
+            None => page_url.to_string(),
+        };
+
+        let mut tags = vec!["a[href]", "area[href]"];
+        if self.link_tags {
+            tags.push("link[href]");
+        }
+        if self.iframes {
+            tags.push("iframe[src]");
+        }
+        let tags = tags.join(", ");
+
+        let links = if self.restrict_css.is_empty() {
+            document.css(&tags)?
+        } else {
+            document
+                .css(&self.restrict_css.join(", "))?
+                .css_or_self(&tags)?
+        };
+
+        let mut seen = HashSet::new();
+        let mut requests = Vec::new();
+        for link in links.iter() {
+            let nofollow = link.attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("nofollow"))
+            });
+            if nofollow {
+                continue;
+            }
+
+            let Some(href) = link.attr("href").or_else(|| link.attr("src")) else {
+                continue;
+            };
+
+            let Ok(mut url) = urljoin(&base, &href).and_then(|url| Ok(Url::parse(&url)?)) else {
+                continue;
+            };
+            url.set_fragment(None);
+            let url = String::from(url);
+
+            // Links are matched and deduplicated in canonical form, but
+            // requested as written.
+            let Ok(canonical) = canonicalize_url(&url) else {
+                continue;
+            };
+            let allowed =
+                self.allow.is_empty() || self.allow.iter().any(|regex| regex.is_match(&canonical));
+            let denied = self.deny.iter().any(|regex| regex.is_match(&canonical));
+            if allowed && !denied && self.is_followable(&canonical) && seen.insert(canonical) {
+                requests.push(Request::new(url));
+            }
+        }
+
+        Ok(requests)
+    }
+
+    /// Whether `url` is an HTTP(S) URL to one of the allowed domains.
+    fn is_followable(&self, url: &str) -> bool {
+        let Ok(url) = Url::parse(url) else {
+            return false;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+
+        let host = url.host_str().unwrap_or_default();
+        self.allow_domains.is_empty()
+            || self.allow_domains.iter().any(|domain| {
+                host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|subdomain| subdomain.ends_with('.'))
+            })
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::request_fingerprint;
+
+/// A request for a page, as returned by a spider's
+/// [`start_requests`](crate::Spider::start_requests) and
+/// [`scrape`](crate::Spider::scrape).
+///
+/// Relative URLs are resolved against the page they were found on before the
+/// request is queued. Everything but the URL is optional, and plain URLs
+/// convert into `GET` requests.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::{Method, Request};
+///
+/// let page: Request = "/page/2".into();
+/// assert_eq!(page.method, Method::GET);
+///
+/// let search = Request::new("/search")
+///     .method(Method::POST)
+///     .header("content-type", "application/x-www-form-urlencoded")
+///     .body("q=rust")
+///     .priority(10)
+///     .callback("parse_results")
+///     .meta("query", "rust");
+/// assert_eq!(search.meta["query"], "rust");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub url: String,
+
+    #[serde(with = "method")]
+    pub method: Method,
+
+    /// Header names and values, sent in order.
+    pub headers: Vec<(String, String)>,
+
+    pub body: Vec<u8>,
+
+    /// Requests with a higher priority are scraped first.
+    pub priority: i32,
+
+    /// The name of the spider callback meant to handle the response, see
+    /// [`Spider::parse`](crate::Spider::parse). The response goes to
+    /// [`Spider::scrape`](crate::Spider::scrape) if `None`.
+    pub callback: Option<String>,
+
+    /// Arbitrary data handed back to the spider when the request is scraped.
+    pub meta: HashMap<String, serde_json::Value>,
+}
+
+impl Request {
+    /// Creates a `GET` request for `url`.
+    pub fn new<U>(url: U) -> Self
+    where
+        U: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            method: Method::GET,
+            headers: Vec::new(),
+            body: Vec::new(),
+            priority: 0,
+            callback: None,
+            meta: HashMap::new(),
+        }
+    }
+
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Appends a header, keeping any previous value for the same name.
+    pub fn header<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn body<B>(mut self, body: B) -> Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.body = body.into();
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn callback<C>(mut self, callback: C) -> Self
+    where
+        C: Into<String>,
+    {
+        self.callback = Some(callback.into());
+        self
+    }
+
+    /// Attaches a metadata entry, replacing any previous value for `key`.
+    pub fn meta<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<serde_json::Value>,
+    {
+        self.meta.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the request's [`request_fingerprint`].
+    pub fn fingerprint(&self) -> u128 {
+        request_fingerprint(self.method.as_str(), &self.url, &self.body)
+    }
+}
+
+impl From<String> for Request {
+    fn from(url: String) -> Self {
+        Self::new(url)
+    }
+}
+
+impl From<&str> for Request {
+    fn from(url: &str) -> Self {
+        Self::new(url)
+    }
+}
+
+/// Serializes methods as their name, since `Method` has no serde support.
+mod method {
+    use reqwest::Method;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(method: &Method, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(method.as_str())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Method, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
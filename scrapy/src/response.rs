@@ -0,0 +1,60 @@
+use std::{borrow::Cow, time::Duration};
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+
+use crate::{urljoin, Document, Error, Request};
+
+/// A downloaded page, handed to the spider to parse.
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// The URL the page was served from, after following redirects.
+    pub url: String,
+
+    pub status: StatusCode,
+
+    /// Header names, lowercased, and values, in the order they were received.
+    pub headers: Vec<(String, String)>,
+
+    pub body: Vec<u8>,
+
+    /// How long the download took.
+    pub elapsed: Duration,
+
+    /// The request the page was downloaded for, with its metadata.
+    pub request: Request,
+}
+
+impl Response {
+    /// Returns the first value of the header `name`, compared ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the body as text, replacing invalid UTF-8 sequences.
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    /// Parses the body as an HTML document, to query it with CSS selectors,
+    /// XPath expressions and regular expressions.
+    pub fn document(&self) -> Document {
+        Document::parse(&self.text())
+    }
+
+    /// Deserializes the body as JSON.
+    pub fn json<T>(&self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Resolves a link found on the page against the page's URL.
+    pub fn urljoin(&self, link: &str) -> Result<String, Error> {
+        urljoin(&self.url, link)
+    }
+}
@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+/// A parsed robots.txt file.
+///
+/// Rules are matched the way RFC 9309 describes: the group naming the
+/// crawler's product token, compared ignoring case, applies, falling back to
+/// the `*` group,
+/// and within a group the longest matching `Allow` or `Disallow` pattern
+/// wins, with `Allow` winning ties. Patterns may use `*` wildcards and a
+/// trailing `$` anchor.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::RobotsTxt;
+///
+/// let robots = RobotsTxt::parse("User-agent: *\nDisallow: /private\nAllow: /private/docs");
+///
+/// assert!(robots.is_allowed("mybot", "/index.html"));
+/// assert!(!robots.is_allowed("mybot", "/private/keys"));
+/// assert!(robots.is_allowed("mybot", "/private/docs/faq"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+    sitemaps: Vec<String>,
+    disallow_all: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    user_agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+impl RobotsTxt {
+    /// Parses the body of a robots.txt file. Unknown and malformed lines are
+    /// ignored.
+    pub fn parse(body: &str) -> Self {
+        let mut robots = Self::default();
+        let mut group = Group::default();
+        let mut in_rules = false;
+
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match field.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group.
+                    if in_rules {
+                        robots.groups.push(std::mem::take(&mut group));
+                        in_rules = false;
+                    }
+                    group.user_agents.push(value.to_ascii_lowercase());
+                }
+                "allow" | "disallow" if !group.user_agents.is_empty() => {
+                    in_rules = true;
+                    // An empty `Disallow` allows everything, like no rule.
+                    if !value.is_empty() {
+                        group.rules.push(Rule {
+                            allow: field.trim().eq_ignore_ascii_case("allow"),
+                            pattern: value.to_string(),
+                        });
+                    }
+                }
+                "crawl-delay" if !group.user_agents.is_empty() => {
+                    in_rules = true;
+                    group.crawl_delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                        .map(Duration::from_secs_f64);
+                }
+                "sitemap" => robots.sitemaps.push(value.to_string()),
+                _ => {}
+            }
+        }
+
+        if !group.user_agents.is_empty() {
+            robots.groups.push(group);
+        }
+
+        robots
+    }
+
+    /// Returns a robots.txt that allows everything, used when a site has none.
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Returns a robots.txt that disallows everything, used when a site's
+    /// robots.txt could not be fetched.
+    pub fn disallow_all() -> Self {
+        Self {
+            disallow_all: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if `user_agent` may crawl `path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - The crawler's user agent, such as `mybot/1.0`, whose
+    ///   product token is matched against the groups' user agents.
+    /// * `path` - The path and query of the URL, starting with `/`.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        if self.disallow_all {
+            return false;
+        }
+
+        let Some(group) = self.group(user_agent) else {
+            return true;
+        };
+
+        group
+            .rules
+            .iter()
+            .filter(|rule| matches(&rule.pattern, path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+
+    /// Returns the `Crawl-delay` that applies to `user_agent`, if any.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.group(user_agent)?.crawl_delay
+    }
+
+    /// Returns the sitemap URLs listed in the file.
+    pub fn sitemaps(&self) -> &[String] {
+        &self.sitemaps
+    }
+
+    fn group(&self, user_agent: &str) -> Option<&Group> {
+        let token = product_token(user_agent);
+        let specific = self
+            .groups
+            .iter()
+            .find(|group| group.user_agents.contains(&token));
+
+        specific.or_else(|| {
+            self.groups
+                .iter()
+                .find(|group| group.user_agents.iter().any(|agent| agent == "*"))
+        })
+    }
+}
+
+/// The product token of a user agent, such as `mybot` for `MyBot/1.0
+/// (+https://mybot.example)`, lowercased.
+fn product_token(user_agent: &str) -> String {
+    let token = user_agent.split('/').next().unwrap_or_default();
+    token.trim().to_ascii_lowercase()
+}
+
+/// Matches a robots.txt pattern against the start of `path`.
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    for (i, part) in parts.iter().enumerate() {
+        // The last part of an anchored pattern must match the end of the path.
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
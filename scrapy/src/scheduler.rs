@@ -0,0 +1,133 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Request, Scheduler};
+
+/// A request waiting to be scraped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedRequest {
+    pub request: Request,
+
+    /// The number of links followed from a start request to reach this one.
+    pub depth: usize,
+
+    /// The number of failed attempts at scraping this request.
+    #[serde(skip)]
+    pub(crate) attempts: usize,
+}
+
+impl QueuedRequest {
+    pub(crate) fn new(request: Request, depth: usize) -> Self {
+        Self {
+            request,
+            depth,
+            attempts: 0,
+        }
+    }
+}
+
+/// How a [`PriorityScheduler`] orders requests of equal priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrawlOrder {
+    /// In the order they were queued.
+    #[default]
+    Fifo,
+
+    /// Shallowest first, so every page at one depth is scraped before any
+    /// page at the next.
+    BreadthFirst,
+
+    /// Most recently queued first, following each chain of links as deep as
+    /// it goes before backtracking.
+    DepthFirst,
+}
+
+/// The default [`Scheduler`], which scrapes higher-priority requests first and
+/// orders requests of equal priority according to its [`CrawlOrder`].
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::{PriorityScheduler, CrawlerBuilder};
+///
+/// let crawler = CrawlerBuilder::new()
+///     .scheduler(PriorityScheduler::breadth_first())
+///     .build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PriorityScheduler {
+    order: CrawlOrder,
+    heap: BinaryHeap<Entry>,
+    next_seq: i64,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: (i32, i64, i64),
+    queued: QueuedRequest,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl PriorityScheduler {
+    pub fn new(order: CrawlOrder) -> Self {
+        Self {
+            order,
+            ..Self::default()
+        }
+    }
+
+    pub fn breadth_first() -> Self {
+        Self::new(CrawlOrder::BreadthFirst)
+    }
+
+    pub fn depth_first() -> Self {
+        Self::new(CrawlOrder::DepthFirst)
+    }
+}
+
+impl Scheduler for PriorityScheduler {
+    fn push(&mut self, queued: QueuedRequest) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        // The heap pops the largest key, so earlier and shallower requests
+        // get negated ranks where they should come first.
+        let priority = queued.request.priority;
+        let depth = queued.depth as i64;
+        let key = match self.order {
+            CrawlOrder::Fifo => (priority, 0, -seq),
+            CrawlOrder::BreadthFirst => (priority, -depth, -seq),
+            CrawlOrder::DepthFirst => (priority, depth, seq),
+        };
+
+        self.heap.push(Entry { key, queued });
+    }
+
+    fn pop(&mut self) -> Option<QueuedRequest> {
+        self.heap.pop().map(|entry| entry.queued)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
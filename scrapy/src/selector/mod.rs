@@ -0,0 +1,310 @@
+use std::{cell::OnceCell, fmt};
+
+use ego_tree::NodeRef;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node};
+
+use crate::SelectorError;
+
+mod xpath;
+
+/// A parsed HTML document, queried with CSS selectors, XPath expressions and
+/// regular expressions.
+///
+/// Queries return a [`Selection`], which can be queried again to narrow it
+/// down. Parse a page once and query it as many times as needed; a document
+/// cannot be sent between threads, so parse it after the spider's last
+/// `.await`.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::Document;
+///
+/// let document = Document::parse(
+///     r#"<div class="quote"><span class="text">Less is &lt;more&gt;</span>
+///        <a href="/author/1">Author</a></div>"#,
+/// );
+///
+/// for quote in document.css(".quote")?.iter() {
+///     assert_eq!(quote.css(".text")?.text().as_deref(), Some("Less is <more>"));
+///     assert_eq!(quote.xpath("a/@href")?.get().as_deref(), Some("/author/1"));
+///     assert_eq!(quote.css("a")?.re_first(r"/author/(\d+)")?.as_deref(), Some("1"));
+/// }
+/// # Ok::<(), scrapy::SelectorError>(())
+/// ```
+pub struct Document {
+    html: Html,
+
+    /// The copy of the document XPath expressions are evaluated against,
+    /// built by the first one.
+    xpath: OnceCell<xpath::XPathDocument>,
+}
+
+impl Document {
+    /// Parses a whole HTML document. Malformed HTML is parsed the way browsers
+    /// do, so parsing never fails.
+    pub fn parse(html: &str) -> Self {
+        Self {
+            html: Html::parse_document(html),
+            xpath: OnceCell::new(),
+        }
+    }
+
+    /// The selection holding the whole document.
+    pub fn root(&self) -> Selection<'_> {
+        Selection {
+            document: self,
+            selected: vec![Selected::Node(self.html.tree.root())],
+        }
+    }
+
+    /// Selects the elements matching a CSS selector, see [`Selection::css`].
+    pub fn css(&self, query: &str) -> Result<Selection<'_>, SelectorError> {
+        self.root().css(query)
+    }
+
+    /// Evaluates an XPath expression, see [`Selection::xpath`].
+    pub fn xpath(&self, query: &str) -> Result<Selection<'_>, SelectorError> {
+        self.root().xpath(query)
+    }
+
+    /// Matches a regular expression against the document, see
+    /// [`Selection::re`].
+    pub fn re(&self, pattern: &str) -> Result<Vec<String>, SelectorError> {
+        self.root().re(pattern)
+    }
+}
+
+impl Clone for Document {
+    fn clone(&self) -> Self {
+        Self {
+            html: self.html.clone(),
+            xpath: OnceCell::new(),
+        }
+    }
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Document")
+            .field("html", &self.html)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What a query selected from a [`Document`], in document order: elements,
+/// or strings such as the attributes and text nodes an XPath expression
+/// selects.
+#[derive(Debug, Clone)]
+pub struct Selection<'a> {
+    document: &'a Document,
+    selected: Vec<Selected<'a>>,
+}
+
+#[derive(Debug, Clone)]
+enum Selected<'a> {
+    /// An element, or the document itself.
+    Node(NodeRef<'a, Node>),
+
+    /// An attribute value, a text node or the result of an XPath function.
+    Text(String),
+}
+
+impl<'a> Selection<'a> {
+    /// The number of selected elements and strings.
+    pub fn len(&self) -> usize {
+        self.selected.len()
+    }
+
+    /// Whether the query selected nothing.
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    /// Iterates over the selection one element or string at a time, such as
+    /// to build an item from each of the selected elements.
+    pub fn iter(&self) -> impl Iterator<Item = Selection<'a>> + '_ {
+        self.selected.iter().map(|selected| Selection {
+            document: self.document,
+            selected: vec![selected.clone()],
+        })
+    }
+
+    /// Selects the descendants of the selected elements matching a CSS
+    /// selector.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The CSS selector, such as `"div.quote > span.text"`.
+    ///
+    /// # Returns
+    ///
+    /// The matching elements, or an error if `query` is not a valid selector.
+    pub fn css(&self, query: &str) -> Result<Selection<'a>, SelectorError> {
+        self.select_css(query, false)
+    }
+
+    /// Like [`css`](Selection::css), but also keeps the selected elements
+    /// that match the selector themselves.
+    pub(crate) fn css_or_self(&self, query: &str) -> Result<Selection<'a>, SelectorError> {
+        self.select_css(query, true)
+    }
+
+    fn select_css(&self, query: &str, or_self: bool) -> Result<Selection<'a>, SelectorError> {
+        let selector = scraper::Selector::parse(query).map_err(|err| SelectorError::Css {
+            query: query.to_string(),
+            message: err.to_string(),
+        })?;
+
+        let mut selected = Vec::new();
+        for node in self.nodes() {
+            match ElementRef::wrap(node) {
+                Some(element) => {
+                    if or_self && selector.matches(&element) {
+                        selected.push(node);
+                    }
+                    selected.extend(element.select(&selector).map(|e| *e));
+                }
+                None => selected.extend(self.document.html.select(&selector).map(|e| *e)),
+            }
+        }
+
+        Ok(self.select(selected.into_iter().map(Selected::Node).collect()))
+    }
+
+    /// Evaluates an XPath 1.0 expression against each of the selected
+    /// elements.
+    ///
+    /// Element names are matched without a namespace, so `//div/p` works on
+    /// HTML documents as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The XPath expression, such as `"//a/@href"`, relative to
+    ///   each selected element.
+    ///
+    /// # Returns
+    ///
+    /// The selected elements, with attributes and text nodes selected as their
+    /// string values, or an error if `query` is not a valid expression or
+    /// fails to evaluate.
+    pub fn xpath(&self, query: &str) -> Result<Selection<'a>, SelectorError> {
+        let scopes = self.nodes().collect::<Vec<_>>();
+        let selected = xpath::select(self.document, &scopes, query)?;
+        Ok(self.select(selected))
+    }
+
+    /// Matches a regular expression against each of the selected elements'
+    /// HTML, as returned by [`get_all`](Selection::get_all).
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The regular expression.
+    ///
+    /// # Returns
+    ///
+    /// Every match, or the text of every capture group that took part in a
+    /// match if the expression has groups. An error if `pattern` is not a
+    /// valid regular expression.
+    pub fn re(&self, pattern: &str) -> Result<Vec<String>, SelectorError> {
+        let regex = Regex::new(pattern)?;
+
+        let mut matches = Vec::new();
+        for haystack in self.get_all() {
+            for captures in regex.captures_iter(&haystack) {
+                if captures.len() == 1 {
+                    matches.push(captures[0].to_string());
+                } else {
+                    let groups = captures.iter().skip(1).flatten();
+                    matches.extend(groups.map(|group| group.as_str().to_string()));
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// The first result of [`re`](Selection::re).
+    pub fn re_first(&self, pattern: &str) -> Result<Option<String>, SelectorError> {
+        Ok(self.re(pattern)?.into_iter().next())
+    }
+
+    /// The HTML of the first selected element, or the first selected string.
+    pub fn get(&self) -> Option<String> {
+        self.selected.first().map(|selected| self.html_of(selected))
+    }
+
+    /// The HTML of every selected element, and every selected string.
+    pub fn get_all(&self) -> Vec<String> {
+        self.selected
+            .iter()
+            .map(|selected| self.html_of(selected))
+            .collect()
+    }
+
+    /// The text of the first selected element, without its tags and with
+    /// character references decoded, trimmed. Strings are returned as is.
+    pub fn text(&self) -> Option<String> {
+        self.selected.first().map(text_of)
+    }
+
+    /// The text of every selected element and every selected string, see
+    /// [`text`](Selection::text).
+    pub fn text_all(&self) -> Vec<String> {
+        self.selected.iter().map(text_of).collect()
+    }
+
+    /// The value of an attribute of the first selected element having it.
+    pub fn attr(&self, name: &str) -> Option<String> {
+        self.attr_all(name).into_iter().next()
+    }
+
+    /// The values of an attribute of every selected element having it.
+    pub fn attr_all(&self, name: &str) -> Vec<String> {
+        self.nodes()
+            .filter_map(ElementRef::wrap)
+            .filter_map(|element| element.attr(name))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn select(&self, selected: Vec<Selected<'a>>) -> Selection<'a> {
+        Selection {
+            document: self.document,
+            selected,
+        }
+    }
+
+    /// The selected elements, leaving strings out.
+    fn nodes(&self) -> impl Iterator<Item = NodeRef<'a, Node>> + '_ {
+        self.selected.iter().filter_map(|selected| match selected {
+            Selected::Node(node) => Some(*node),
+            Selected::Text(_) => None,
+        })
+    }
+
+    fn html_of(&self, selected: &Selected<'a>) -> String {
+        match selected {
+            Selected::Node(node) => match ElementRef::wrap(*node) {
+                Some(element) => element.html(),
+                None => self.document.html.html(),
+            },
+            Selected::Text(text) => text.clone(),
+        }
+    }
+}
+
+fn text_of(selected: &Selected<'_>) -> String {
+    match selected {
+        Selected::Node(node) => {
+            let text = node
+                .descendants()
+                .filter_map(|node| node.value().as_text())
+                .map(|text| &**text)
+                .collect::<String>();
+            text.trim().to_string()
+        }
+        Selected::Text(text) => text.clone(),
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use ego_tree::{NodeId, NodeRef};
+use scraper::{Html, Node};
+use self_cell::self_cell;
+use sxd_document::{dom, Package};
+use sxd_xpath::{nodeset, Context, Factory, Value};
+
+use crate::SelectorError;
+
+use super::{Document, Selected};
+
+/// Evaluates an XPath expression against each of `scopes`.
+///
+/// XPath is evaluated against a copy of the document built for the purpose
+/// the first time it is queried with XPath, whose nodes are mapped back to
+/// the document's.
+pub(super) fn select<'a>(
+    document: &'a Document,
+    scopes: &[NodeRef<'a, Node>],
+    query: &str,
+) -> Result<Vec<Selected<'a>>, SelectorError> {
+    let error = |message: String| SelectorError::XPath {
+        query: query.to_string(),
+        message,
+    };
+
+    let xpath = Factory::new()
+        .build(query)
+        .map_err(|err| error(err.to_string()))?
+        .ok_or_else(|| error(String::from("the expression is empty")))?;
+
+    let html = &document.html;
+    let copy = document
+        .xpath
+        .get_or_init(|| XPathDocument::build(html))
+        .copy();
+    let context = Context::new();
+
+    let mut selected = Vec::new();
+    for scope in scopes {
+        let value = xpath
+            .evaluate(&context, copy.node(scope.id()))
+            .map_err(|err| error(err.to_string()))?;
+
+        match value {
+            Value::Nodeset(nodes) => {
+                for node in nodes.document_order() {
+                    selected.push(match copy.original(node) {
+                        Some(id) => Selected::Node(html.tree.get(id).unwrap()),
+                        None => Selected::Text(node.string_value()),
+                    });
+                }
+            }
+            Value::String(text) => selected.push(Selected::Text(text)),
+            Value::Number(number) => selected.push(Selected::Text(format_number(number))),
+            Value::Boolean(boolean) => selected.push(Selected::Text(boolean.to_string())),
+        }
+    }
+
+    Ok(selected)
+}
+
+self_cell!(
+    /// A [`Copy`] of an HTML document along with the package holding its nodes.
+    pub(super) struct XPathDocument {
+        owner: Package,
+
+        #[covariant]
+        dependent: Copy,
+    }
+);
+
+impl XPathDocument {
+    fn build(html: &Html) -> Self {
+        Self::new(Package::new(), |package| {
+            Copy::new(package.as_document(), html)
+        })
+    }
+
+    fn copy(&self) -> &Copy<'_> {
+        self.borrow_dependent()
+    }
+}
+
+/// A copy of an HTML document in a form XPath can be evaluated against.
+struct Copy<'d> {
+    root: dom::Root<'d>,
+    root_id: NodeId,
+    elements: HashMap<NodeId, dom::Element<'d>>,
+    ids: HashMap<dom::Element<'d>, NodeId>,
+}
+
+impl<'d> Copy<'d> {
+    fn new(document: dom::Document<'d>, html: &Html) -> Self {
+        let root = html.tree.root();
+        let mut copy = Self {
+            root: document.root(),
+            root_id: root.id(),
+            elements: HashMap::new(),
+            ids: HashMap::new(),
+        };
+
+        // Documents can nest deeper than the stack allows for recursion.
+        let mut pending = root
+            .children()
+            .rev()
+            .map(|child| (Parent::Root(copy.root), child))
+            .collect::<Vec<_>>();
+
+        while let Some((parent, node)) = pending.pop() {
+            let child = match node.value() {
+                Node::Element(element) => {
+                    let copied = document.create_element(element.name());
+                    for (name, value) in element.attrs() {
+                        copied.set_attribute_value(name, value);
+                    }
+
+                    copy.elements.insert(node.id(), copied);
+                    copy.ids.insert(copied, node.id());
+                    pending.extend(
+                        node.children()
+                            .rev()
+                            .map(|child| (Parent::Element(copied), child)),
+                    );
+                    dom::ChildOfElement::Element(copied)
+                }
+                Node::Text(text) => document.create_text(text).into(),
+                Node::Comment(comment) => document.create_comment(comment).into(),
+                _ => continue,
+            };
+
+            match parent {
+                Parent::Root(root) => {
+                    if let dom::ChildOfElement::Element(element) = child {
+                        root.append_child(element);
+                    }
+                }
+                Parent::Element(element) => element.append_child(child),
+            }
+        }
+
+        copy
+    }
+
+    /// The copy of a node of the document.
+    fn node(&self, id: NodeId) -> nodeset::Node<'d> {
+        match self.elements.get(&id) {
+            Some(element) => (*element).into(),
+            None => self.root.into(),
+        }
+    }
+
+    /// The node of the document a node of the copy is a copy of, if it is an
+    /// element or the document itself.
+    fn original(&self, node: nodeset::Node<'d>) -> Option<NodeId> {
+        match node {
+            nodeset::Node::Root(_) => Some(self.root_id),
+            nodeset::Node::Element(element) => self.ids.get(&element).copied(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Parent<'d> {
+    Root(dom::Root<'d>),
+    Element(dom::Element<'d>),
+}
+
+/// Formats numbers the way XPath's `string()` does, so `count()` gives `3`
+/// rather than `3.0`.
+fn format_number(number: f64) -> String {
+    if number.is_finite() && number.fract() == 0.0 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}
@@ -0,0 +1,327 @@
+use std::io::{self, Read};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use regex::Regex;
+
+use crate::{Request, Response, RobotsTxt, SelectorError, Spider};
+
+/// The callback of requests for sitemaps and robots.txt files, which are
+/// handled by the [`SitemapSpider`] itself.
+const SITEMAP_CALLBACK: &str = "sitemap_spider_sitemap";
+
+/// The largest a sitemap may be once decompressed, as set by the sitemaps
+/// protocol, so a small gzipped file cannot exhaust the memory.
+const MAX_SITEMAP_SIZE: u64 = 50 * 1024 * 1024;
+
+/// A [`Spider`] crawling the pages listed in a site's sitemaps, rather than
+/// following links.
+///
+/// Crawling starts from sitemap URLs, or robots.txt URLs whose `Sitemap:`
+/// lines are used. Both `<urlset>` sitemaps and `<sitemapindex>` indexes are
+/// read, whether gzipped or not, and indexes are followed down to their
+/// sitemaps. Sitemaps larger than 50 MiB once decompressed are skipped, like
+/// malformed ones.
+///
+/// The pages a sitemap lists are matched against the spider's rules, in
+/// order. A page matching a rule is scraped by the wrapped spider's
+/// [`parse`](Spider::parse) with the rule's callback, and pages matching no
+/// rule are skipped. Without rules, every page is scraped by the wrapped
+/// spider's [`scrape`](Spider::scrape).
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use scrapy::SitemapSpider;
+/// # use async_trait::async_trait;
+/// # use scrapy::{Request, Response, Spider};
+/// #
+/// # struct ShopSpider;
+/// #
+/// # #[async_trait]
+/// # impl Spider for ShopSpider {
+/// #     type Item = String;
+/// #     type Error = scrapy::Error;
+/// #
+/// #     fn name(&self) -> String {
+/// #         String::from("shop")
+/// #     }
+/// #
+/// #     async fn scrape(&self, _: &Response) -> Result<(Vec<String>, Vec<Request>), scrapy::Error> {
+/// #         Ok((Vec::new(), Vec::new()))
+/// #     }
+/// # }
+///
+/// let spider = SitemapSpider::new(ShopSpider)
+///     .sitemap_url("https://shop.example/robots.txt")
+///     .follow("/sitemap-products")?
+///     .rule("/product/", "parse_product")?
+///     .rule("/category/", "parse_category")?
+///     .modified_since(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap());
+/// # Ok::<(), scrapy::SelectorError>(())
+/// ```
+pub struct SitemapSpider<S> {
+    spider: S,
+    sitemap_urls: Vec<String>,
+    rules: Vec<(Regex, String)>,
+    follow: Vec<Regex>,
+    modified_since: Option<DateTime<Utc>>,
+}
+
+/// An entry of a sitemap or sitemap index.
+struct Entry {
+    loc: String,
+    lastmod: Option<DateTime<Utc>>,
+}
+
+impl<S> SitemapSpider<S> {
+    /// Creates a sitemap spider without sitemaps, scraping pages with
+    /// `spider`.
+    pub fn new(spider: S) -> Self {
+        Self {
+            spider,
+            sitemap_urls: Vec::new(),
+            rules: Vec::new(),
+            follow: Vec::new(),
+            modified_since: None,
+        }
+    }
+
+    /// Adds a sitemap, sitemap index or robots.txt to start from. URLs whose
+    /// path ends with `/robots.txt` are read as robots.txt files.
+    pub fn sitemap_url<U>(mut self, url: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.sitemap_urls.push(url.into());
+        self
+    }
+
+    /// Scrapes the pages whose URL matches the regular expression `pattern`
+    /// with `callback`, see [`Spider::parse`]. Rules are tried in the order
+    /// they are added.
+    ///
+    /// # Returns
+    ///
+    /// The spider, or an error if `pattern` is not a valid regular
+    /// expression.
+    pub fn rule<P, C>(mut self, pattern: P, callback: C) -> Result<Self, SelectorError>
+    where
+        P: AsRef<str>,
+        C: Into<String>,
+    {
+        self.rules
+            .push((Regex::new(pattern.as_ref())?, callback.into()));
+        Ok(self)
+    }
+
+    /// Only follows the sitemaps of an index whose URL matches the regular
+    /// expression `pattern`, or one of the other follow patterns. Every
+    /// sitemap is followed by default.
+    ///
+    /// # Returns
+    ///
+    /// The spider, or an error if `pattern` is not a valid regular
+    /// expression.
+    pub fn follow<P>(mut self, pattern: P) -> Result<Self, SelectorError>
+    where
+        P: AsRef<str>,
+    {
+        self.follow.push(Regex::new(pattern.as_ref())?);
+        Ok(self)
+    }
+
+    /// Skips the pages and sitemaps whose `<lastmod>` is older than `since`.
+    /// Entries without a `<lastmod>` are kept.
+    pub fn modified_since(mut self, since: DateTime<Utc>) -> Self {
+        self.modified_since = Some(since);
+        self
+    }
+
+    /// The wrapped spider.
+    pub fn spider(&self) -> &S {
+        &self.spider
+    }
+
+    /// Reads a sitemap, sitemap index or robots.txt, returning requests for
+    /// the sitemaps and pages it lists.
+    fn read_sitemap(&self, response: &Response) -> Vec<Request> {
+        let path = url::Url::parse(&response.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+        if path.ends_with("/robots.txt") {
+            let robots = RobotsTxt::parse(&response.text());
+            return robots.sitemaps().iter().map(sitemap_request).collect();
+        }
+
+        let body = match sitemap_body(&response.body) {
+            Ok(body) => body,
+            Err(err) => {
+                log::warn!("ignoring sitemap {}: {}", response.url, err);
+                return Vec::new();
+            }
+        };
+        let document = match roxmltree::Document::parse(&body) {
+            Ok(document) => document,
+            Err(err) => {
+                log::warn!("ignoring sitemap {}: {}", response.url, err);
+                return Vec::new();
+            }
+        };
+
+        let root = document.root_element();
+        match root.tag_name().name() {
+            "sitemapindex" => entries(root, "sitemap")
+                .filter(|entry| self.is_fresh(entry))
+                .filter(|entry| {
+                    self.follow.is_empty()
+                        || self.follow.iter().any(|regex| regex.is_match(&entry.loc))
+                })
+                .map(|entry| sitemap_request(&entry.loc))
+                .collect(),
+            "urlset" => {
+                let mut requests = Vec::new();
+                for entry in entries(root, "url").filter(|entry| self.is_fresh(entry)) {
+                    if self.rules.is_empty() {
+                        requests.push(Request::new(entry.loc));
+                    } else if let Some((_, callback)) = self
+                        .rules
+                        .iter()
+                        .find(|(regex, _)| regex.is_match(&entry.loc))
+                    {
+                        requests.push(Request::new(entry.loc).callback(callback.as_str()));
+                    }
+                }
+                requests
+            }
+            tag => {
+                log::warn!("ignoring sitemap {}: unknown root <{}>", response.url, tag);
+                Vec::new()
+            }
+        }
+    }
+
+    fn is_fresh(&self, entry: &Entry) -> bool {
+        match (self.modified_since, entry.lastmod) {
+            (Some(since), Some(lastmod)) => lastmod >= since,
+            _ => true,
+        }
+    }
+}
+
+#[async_trait]
+impl<S> Spider for SitemapSpider<S>
+where
+    S: Spider,
+    S::Item: Send,
+    S::Error: Send,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn name(&self) -> String {
+        self.spider.name()
+    }
+
+    fn start_requests(&self) -> Vec<Request> {
+        self.sitemap_urls.iter().map(sitemap_request).collect()
+    }
+
+    async fn scrape(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<Self::Item>, Vec<Request>), Self::Error> {
+        self.spider.scrape(response).await
+    }
+
+    async fn parse(
+        &self,
+        callback: &str,
+        response: &Response,
+    ) -> Option<Result<(Vec<Self::Item>, Vec<Request>), Self::Error>> {
+        if callback != SITEMAP_CALLBACK {
+            return self.spider.parse(callback, response).await;
+        }
+
+        Some(Ok((Vec::new(), self.read_sitemap(response))))
+    }
+
+    fn is_retryable(&self, error: &Self::Error) -> bool {
+        self.spider.is_retryable(error)
+    }
+}
+
+fn sitemap_request<U>(url: U) -> Request
+where
+    U: Into<String>,
+{
+    Request::new(url).callback(SITEMAP_CALLBACK)
+}
+
+/// The text of a sitemap, decompressing gzipped sitemaps.
+///
+/// # Returns
+///
+/// The text, or an error if a gzipped sitemap cannot be decompressed or is
+/// larger than [`MAX_SITEMAP_SIZE`] once decompressed.
+fn sitemap_body(body: &[u8]) -> io::Result<String> {
+    if !body.starts_with(&[0x1f, 0x8b]) {
+        return Ok(String::from_utf8_lossy(body).into_owned());
+    }
+
+    // Reading one byte past the limit tells a sitemap of exactly the limit
+    // from a larger one, without decompressing the rest of it.
+    let mut text = Vec::new();
+    GzDecoder::new(body)
+        .take(MAX_SITEMAP_SIZE + 1)
+        .read_to_end(&mut text)?;
+    if text.len() as u64 > MAX_SITEMAP_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "larger than 50 MiB once decompressed",
+        ));
+    }
+
+    String::from_utf8(text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// The `<loc>` and `<lastmod>` of the `tag` children of a sitemap's root,
+/// skipping entries without a location.
+fn entries<'a>(root: roxmltree::Node<'a, 'a>, tag: &'a str) -> impl Iterator<Item = Entry> + 'a {
+    root.children()
+        .filter(move |node| node.tag_name().name() == tag)
+        .filter_map(|node| {
+            let child = |name: &str| {
+                node.children()
+                    .find(|child| child.tag_name().name() == name)
+                    .and_then(|child| child.text())
+                    .map(str::trim)
+            };
+
+            Some(Entry {
+                loc: child("loc").filter(|loc| !loc.is_empty())?.to_string(),
+                lastmod: child("lastmod").and_then(parse_lastmod),
+            })
+        })
+}
+
+/// Parses a W3C datetime, such as `2023-11-05` or `2023-11-05T14:30:00+01:00`.
+fn parse_lastmod(lastmod: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(lastmod) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+
+    // Seconds are optional in W3C datetimes.
+    let offset = lastmod
+        .strip_suffix('Z')
+        .map(|datetime| format!("{}+00:00", datetime));
+    let lastmod_with_offset = offset.as_deref().unwrap_or(lastmod);
+    if let Ok(datetime) = DateTime::parse_from_str(lastmod_with_offset, "%Y-%m-%dT%H:%M%:z") {
+        return Some(datetime.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(lastmod, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+/// A thread-safe store of the numbers a crawl keeps track of, shared by the
+/// crawler, its spiders and its pipelines.
+///
+/// Stats are either counters, which only go up, or gauges, which are set to
+/// a value. The crawler owns a collector and counts under these keys:
+///
+/// * `downloader/request_count` - Requests handed to the downloader.
+/// * `downloader/response_count` - Responses downloaded, whatever their status.
+/// * `downloader/response_status_count/<status>` - Responses by status code.
+/// * `downloader/response_bytes` - The size of the downloaded bodies.
+/// * `request_depth_count/<depth>` - Requests queued at each depth, and the
+///   `request_depth_max` gauge.
+/// * `item_scraped_count`, `item_processed_count` and `item_dropped_count` -
+///   Items returned by the spider, through every pipeline and dropped.
+/// * `error_count/<type>` - Errors by type: `scrape`, `callback`, `pipeline`
+///   and `download/<kind>`, such as `download/http`.
+/// * `retry_count` and `robots_blocked_count`.
+///
+/// Spiders and pipelines keep a clone of the collector handed to
+/// [`CrawlerBuilder::stats`](crate::CrawlerBuilder::stats) to record their
+/// own stats. Stats are logged periodically while crawling, and end up in the
+/// [`CrawlReport`](crate::CrawlReport).
+///
+/// Stats belong to the collector rather than to a crawl: a collector is never
+/// cleared by the crawler, so stats recorded before a crawl are kept, and a
+/// collector shared by several crawls, including successive crawls of the
+/// same crawler, adds their stats up. Give each crawl its own collector, or
+/// [`clear`](StatsCollector::clear) it between crawls, to tell them apart.
+///
+/// # Examples
+///
+/// ```
+/// use scrapy::{CrawlerBuilder, StatsCollector};
+///
+/// let stats = StatsCollector::new();
+/// let crawler = CrawlerBuilder::new().stats(stats.clone()).build();
+///
+/// stats.inc("books/out_of_stock");
+/// stats.set("queue/size", 42);
+/// assert_eq!(stats.counter("books/out_of_stock"), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StatsCollector {
+    stats: Arc<Mutex<Stats>>,
+}
+
+/// The stats of a [`StatsCollector`] at some point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub counters: BTreeMap<String, u64>,
+    pub gauges: BTreeMap<String, i64>,
+}
+
+impl StatsCollector {
+    /// Creates a collector without stats.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds one to a counter, starting from zero.
+    ///
+    /// # Returns
+    ///
+    /// The counter's new value.
+    pub fn inc(&self, key: &str) -> u64 {
+        self.inc_by(key, 1)
+    }
+
+    /// Adds `by` to a counter, starting from zero.
+    ///
+    /// # Returns
+    ///
+    /// The counter's new value.
+    pub fn inc_by(&self, key: &str, by: u64) -> u64 {
+        let mut stats = self.stats.lock().unwrap();
+        let counter = match stats.counters.get_mut(key) {
+            Some(counter) => counter,
+            None => stats.counters.entry(key.to_string()).or_default(),
+        };
+        *counter += by;
+        *counter
+    }
+
+    /// Sets a gauge.
+    pub fn set(&self, key: &str, value: i64) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.gauges.insert(key.to_string(), value);
+    }
+
+    /// Sets a gauge to `value` if it is unset or lower.
+    pub fn set_max(&self, key: &str, value: i64) {
+        let mut stats = self.stats.lock().unwrap();
+        let gauge = stats.gauges.entry(key.to_string()).or_insert(value);
+        *gauge = (*gauge).max(value);
+    }
+
+    /// Sets a gauge to `value` if it is unset or higher.
+    pub fn set_min(&self, key: &str, value: i64) {
+        let mut stats = self.stats.lock().unwrap();
+        let gauge = stats.gauges.entry(key.to_string()).or_insert(value);
+        *gauge = (*gauge).min(value);
+    }
+
+    /// The value of a counter, zero if it was never incremented.
+    pub fn counter(&self, key: &str) -> u64 {
+        self.stats.lock().unwrap().counter(key)
+    }
+
+    /// The value of a gauge, if it was set.
+    pub fn gauge(&self, key: &str) -> Option<i64> {
+        self.stats.lock().unwrap().gauge(key)
+    }
+
+    /// A copy of every stat.
+    pub fn snapshot(&self) -> Stats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Removes every stat.
+    pub fn clear(&self) {
+        *self.stats.lock().unwrap() = Stats::default();
+    }
+}
+
+impl Stats {
+    /// The value of a counter, zero if it was never incremented.
+    pub fn counter(&self, key: &str) -> u64 {
+        self.counters.get(key).copied().unwrap_or(0)
+    }
+
+    /// The value of a gauge, if it was set.
+    pub fn gauge(&self, key: &str) -> Option<i64> {
+        self.gauges.get(key).copied()
+    }
+}
+
+/// Lists the stats one per line, sorted by key.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stats = self
+            .counters
+            .iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .chain(
+                self.gauges
+                    .iter()
+                    .map(|(key, value)| (key, value.to_string())),
+            )
+            .collect::<Vec<_>>();
+        stats.sort();
+
+        for (key, value) in stats {
+            writeln!(f, "{}: {}", key, value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+
+use crate::{Error, Request, Response};
+
+/// A trait for fetching the pages a crawl requests.
+///
+/// Every request is fetched through the crawler's downloader before the
+/// spider sees it, so timeouts, headers and cookies are shared by the whole
+/// crawl. The default implementation is
+/// [`HttpDownloader`](crate::HttpDownloader).
+#[async_trait]
+pub trait Downloader: Send + Sync {
+    /// Fetches a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to fetch, with its URL resolved against the
+    ///   page it was found on but otherwise as written.
+    ///
+    /// # Returns
+    ///
+    /// The response, whatever its status, or an error if none was received.
+    async fn fetch(&self, request: &Request) -> Result<Response, Error>;
+}
@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+
+use crate::{Error, Request, Response};
+
+/// A trait for hooking into every download of a crawl, to add headers, set
+/// proxies, retry on some statuses and the like without touching the spider.
+///
+/// Middlewares are registered in order with
+/// [`CrawlerBuilder::downloader_middleware`](crate::CrawlerBuilder::downloader_middleware).
+/// Requests pass through them in that order on their way to the
+/// [`Downloader`](crate::Downloader), and responses and errors pass back
+/// through them in reverse order. Every hook does nothing by default.
+#[async_trait]
+pub trait DownloaderMiddleware: Send + Sync {
+    /// Called on every request before it is downloaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request about to be downloaded, which the middleware
+    ///   may change.
+    ///
+    /// # Returns
+    ///
+    /// `None` to carry on with the download, or a response to use instead of
+    /// downloading the request. Such a response skips the remaining
+    /// `process_request` hooks and the downloader, but still goes through
+    /// every `process_response` hook. An error fails the download and goes
+    /// through every `process_error` hook.
+    async fn process_request(&self, _request: &mut Request) -> Result<Option<Response>, Error> {
+        Ok(None)
+    }
+
+    /// Called on every response before the spider sees it.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The response, with the request it was downloaded for.
+    ///
+    /// # Returns
+    ///
+    /// The response handed to the next middleware, or an error failing the
+    /// download, which is then retried according to the crawler's
+    /// [`RetryPolicy`](crate::RetryPolicy).
+    async fn process_response(&self, response: Response) -> Result<Response, Error> {
+        Ok(response)
+    }
+
+    /// Called when the downloader or a `process_request` hook fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request whose download failed.
+    /// * `error` - The error the download failed with.
+    ///
+    /// # Returns
+    ///
+    /// The error, possibly replaced, handed to the next middleware, or a
+    /// response recovering from it, which skips the remaining
+    /// `process_error` hooks and goes through every `process_response` hook.
+    async fn process_error(&self, _request: &Request, error: Error) -> Result<Response, Error> {
+        Err(error)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::Request;
+
+/// A trait for deciding whether a request has already been scheduled during a
+/// crawl.
+///
+/// Every request goes through the filter before it is queued, so a request the
+/// filter reports as seen is never crawled twice. The default implementation
+/// is [`FingerprintDupeFilter`](crate::FingerprintDupeFilter).
+pub trait DupeFilter: Send {
+    /// Checks whether a request has been seen before, recording it if not.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request about to be queued, with its URL resolved
+    ///   but not canonicalized. Filters wanting URLs that differ only in
+    ///   form to be duplicates can compare
+    ///   [`Request::fingerprint`](crate::Request::fingerprint)s, as the
+    ///   default one does.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the request is a duplicate and should be dropped.
+    fn request_seen(&mut self, request: &Request) -> bool;
+}
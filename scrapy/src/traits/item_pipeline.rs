@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+
+use crate::{Error, ItemError};
+
+/// A trait for a stage that every item scraped by a crawl goes through, such
+/// as validating, cleaning, deduplicating or storing items.
+///
+/// Pipelines are chained in order with
+/// [`CrawlerBuilder::item_pipeline`](crate::CrawlerBuilder::item_pipeline),
+/// each stage handing the item it returns to the next one. A pipeline is
+/// generic over the items it accepts, so the same pipeline can serve every
+/// spider producing that type of item.
+#[async_trait]
+pub trait ItemPipeline<T>: Send + Sync {
+    /// Called once when a crawl starts, before any item is processed. Does
+    /// nothing by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `spider` - The name of the spider being crawled.
+    ///
+    /// # Returns
+    ///
+    /// An error if the pipeline cannot be used, which closes the crawl
+    /// before it starts.
+    async fn open(&self, _spider: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Processes a scraped item.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item returned by the spider, as changed by the previous
+    ///   stages.
+    ///
+    /// # Returns
+    ///
+    /// The item to hand to the next stage, or an [`ItemError`] dropping or
+    /// failing it, which skips the remaining stages.
+    async fn process_item(&self, item: T) -> Result<T, ItemError>;
+
+    /// Called once when a crawl finishes, after every item was processed.
+    /// Does nothing by default.
+    ///
+    /// # Returns
+    ///
+    /// An error if the pipeline could not finish its work, which is logged.
+    async fn close(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -1,5 +1,20 @@
+mod downloader;
+pub use downloader::Downloader;
+
+mod downloader_middleware;
+pub use downloader_middleware::DownloaderMiddleware;
+
+mod dupe_filter;
+pub use dupe_filter::DupeFilter;
+
 mod from_html;
 pub use from_html::FromHTML;
 
+mod item_pipeline;
+pub use item_pipeline::ItemPipeline;
+
+mod scheduler;
+pub use scheduler::Scheduler;
+
 mod spider;
 pub use spider::Spider;
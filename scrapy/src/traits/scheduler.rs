@@ -0,0 +1,30 @@
+use crate::QueuedRequest;
+
+/// A trait for deciding the order in which queued requests are scraped.
+///
+/// The crawler pushes every request that passes the dupe filter and pops the
+/// next one whenever a scrape can start. The default implementation is a
+/// [`PriorityScheduler`](crate::PriorityScheduler) in FIFO order.
+pub trait Scheduler: Send {
+    /// Adds a request to the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `queued` - The request to schedule, with its depth.
+    fn push(&mut self, queued: QueuedRequest);
+
+    /// Takes the request to scrape next.
+    ///
+    /// # Returns
+    ///
+    /// The next request, or `None` if the queue is empty.
+    fn pop(&mut self) -> Option<QueuedRequest>;
+
+    /// Returns the number of queued requests.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if no request is queued.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
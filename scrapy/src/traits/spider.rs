@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 
-/// An asynchronous trait defining behavior for web spiders, capable of crawling,
-/// scraping, and processing content from web pages.
+use crate::{Request, Response};
+
+/// An asynchronous trait defining behavior for web spiders, capable of crawling
+/// and scraping content from web pages. The items a spider scrapes are handed
+/// to the crawler's [`ItemPipeline`](crate::ItemPipeline)s.
 #[async_trait]
 pub trait Spider: Send + Sync {
     /// The type of items that the spider extracts from web pages.
@@ -17,33 +20,82 @@ pub trait Spider: Send + Sync {
     /// A string representing the name of the spider.
     fn name(&self) -> String;
 
-    /// Retrieves the initial URLs for the spider to begin crawling.
+    /// Retrieves the initial URLs for the spider to begin crawling. Spiders
+    /// that need more than a `GET` request per URL override
+    /// [`start_requests`](Spider::start_requests) instead.
     ///
     /// # Returns
     ///
     /// A vector of strings containing the starting URLs.
-    fn start_urls(&self) -> Vec<String>;
+    fn start_urls(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Retrieves the initial requests for the spider to begin crawling.
+    ///
+    /// # Returns
+    ///
+    /// A vector of requests, by default a `GET` request for each of the
+    /// [`start_urls`](Spider::start_urls).
+    fn start_requests(&self) -> Vec<Request> {
+        self.start_urls().into_iter().map(Request::from).collect()
+    }
+
+    /// Asynchronously scrapes content from a downloaded page.
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The page fetched by the crawler's
+    ///   [`Downloader`](crate::Downloader), whatever its status, along with
+    ///   the request it was fetched for.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a tuple with extracted items and new requests,
+    /// or an error describing the scraping failure. Relative URLs in new
+    /// requests are resolved against the response's URL.
+    async fn scrape(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<Self::Item>, Vec<Request>), Self::Error>;
 
-    /// Asynchronously scrapes content from a given URL.
+    /// Asynchronously scrapes a page whose request named a
+    /// [`callback`](Request::callback), so one spider can handle different
+    /// kinds of pages with different functions. Requests without a callback
+    /// are scraped by [`scrape`](Spider::scrape).
     ///
     /// # Arguments
     ///
-    /// * `url` - A string representing the URL to be scraped.
+    /// * `callback` - The name of the callback, such as `"parse_product"`.
+    /// * `response` - The page fetched for the request.
     ///
     /// # Returns
     ///
-    /// A `Result` containing a tuple with extracted items and new URLs,
-    /// or an error describing the scraping failure.
-    async fn scrape(&self, url: &str) -> Result<(Vec<Self::Item>, Vec<String>), Self::Error>;
+    /// The result of the callback, like `scrape`'s, or `None` if the spider
+    /// has no callback with that name, in which case the request fails
+    /// without being retried. Spiders without callbacks return `None` by
+    /// default.
+    async fn parse(
+        &self,
+        _callback: &str,
+        _response: &Response,
+    ) -> Option<Result<(Vec<Self::Item>, Vec<Request>), Self::Error>> {
+        None
+    }
 
-    /// Asynchronously processes an extracted item.
+    /// Decides whether a failed scrape is worth retrying, when the crawler has
+    /// a [`RetryPolicy`](crate::RetryPolicy). Every error is retried by
+    /// default. Failed downloads are always retried, except for pages missing
+    /// from an offline [`HttpCache`](crate::HttpCache).
     ///
     /// # Arguments
     ///
-    /// * `item` - The item extracted from a web page.
+    /// * `error` - The error returned by `scrape` or a callback.
     ///
     /// # Returns
     ///
-    /// A `Result` indicating the success or failure of the processing operation.
-    async fn process(&self, item: Self::Item) -> Result<(), Self::Error>;
+    /// `true` if the request should be scraped again.
+    fn is_retryable(&self, _error: &Self::Error) -> bool {
+        true
+    }
 }
@@ -0,0 +1,99 @@
+use scraper::{Html, Selector};
+use url::{form_urlencoded, Url};
+
+use crate::Error;
+
+/// Canonicalizes a URL so that equivalent URLs compare equal.
+///
+/// The scheme and host are lowercased, international domain names are
+/// converted to punycode, default ports, dot segments and the fragment are
+/// removed, and the query parameters are sorted. Parameters are sorted as
+/// they are written, without decoding them.
+///
+/// The canonical form identifies a URL, such as to find duplicate requests,
+/// but the server may not see it as the same resource: requests are sent to
+/// their URL as given.
+///
+/// # Arguments
+///
+/// * `url` - An absolute URL.
+///
+/// # Returns
+///
+/// The canonical form of the URL, or an error if it cannot be parsed.
+pub fn canonicalize_url(url: &str) -> Result<String, Error> {
+    Ok(canonicalize(Url::parse(url.trim())?, &[]).into())
+}
+
+/// Resolves a link against the URL of the page it was found on.
+///
+/// # Arguments
+///
+/// * `base` - The absolute URL relative links are resolved against.
+/// * `link` - An absolute or relative link, such as `../page-2.html`.
+///
+/// # Returns
+///
+/// The absolute URL of the link, or an error if it cannot be resolved.
+pub fn urljoin(base: &str, link: &str) -> Result<String, Error> {
+    Ok(Url::parse(base.trim())?.join(link.trim())?.into())
+}
+
+/// Finds the URL that relative links on a page resolve against.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page.
+/// * `page_url` - The absolute URL the page was fetched from.
+///
+/// # Returns
+///
+/// The page's `<base href>` resolved against `page_url` if it has one,
+/// `page_url` otherwise.
+pub fn base_url(html: &str, page_url: &str) -> Result<String, Error> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("base[href]").expect("valid selector");
+
+    match document
+        .select(&selector)
+        .next()
+        .and_then(|base| base.value().attr("href"))
+    {
+        Some(href) => urljoin(page_url, href),
+        None => Ok(Url::parse(page_url.trim())?.into()),
+    }
+}
+
+/// Canonicalizes a parsed URL, dropping the query parameters in `ignored_params`.
+pub(crate) fn canonicalize(mut url: Url, ignored_params: &[String]) -> Url {
+    url.set_fragment(None);
+
+    let mut params = url
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty() && !is_ignored(param, ignored_params))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    params.sort();
+
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.set_query(Some(&params.join("&")));
+    }
+
+    url
+}
+
+/// Whether the name of a raw `name=value` query parameter, once decoded, is
+/// one of `ignored_params`.
+fn is_ignored(param: &str, ignored_params: &[String]) -> bool {
+    let name = param.split('=').next().unwrap_or_default();
+    let decoded = form_urlencoded::parse(name.as_bytes())
+        .next()
+        .map(|(name, _)| name);
+    ignored_params
+        .iter()
+        .any(|ignored| ignored == name || decoded.as_deref() == Some(ignored.as_str()))
+}
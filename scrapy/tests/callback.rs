@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use scrapy::{CrawlerBuilder, Request, Response, RetryPolicy, ScrapeFailure, Spider};
+
+use common::{Collect, StubDownloader};
+
+mod common;
+
+/// A spider whose listing links to two products, each handled by the
+/// `parse_product` callback, and to a page tagged with a callback the spider
+/// does not have.
+struct ShopSpider;
+
+impl ShopSpider {
+    fn parse_product(&self, response: &Response) -> (Vec<String>, Vec<Request>) {
+        (vec![format!("product {}", response.url)], Vec::new())
+    }
+}
+
+#[async_trait]
+impl Spider for ShopSpider {
+    type Item = String;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("shop")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://shop.test/")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<String>, Vec<Request>), String> {
+        let links = vec![
+            Request::new("/products/1").callback("parse_product"),
+            Request::new("/products/2").callback("parse_product"),
+            Request::new("/reviews").callback("parse_review"),
+        ];
+
+        Ok((vec![format!("listing {}", response.url)], links))
+    }
+
+    async fn parse(
+        &self,
+        callback: &str,
+        response: &Response,
+    ) -> Option<Result<(Vec<String>, Vec<Request>), String>> {
+        match callback {
+            "parse_product" => Some(Ok(self.parse_product(response))),
+            _ => None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn requests_are_dispatched_to_their_callback() {
+    let retry = RetryPolicy::new()
+        .max_attempts(3)
+        .backoff(Duration::from_millis(10))
+        .jitter(false);
+    let collect = Collect::default();
+    let crawler = CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .retry(retry)
+        .item_pipeline(collect.clone())
+        .build();
+
+    let report = crawler.crawl(ShopSpider).await.unwrap();
+
+    let items = collect.sorted();
+    assert_eq!(
+        items,
+        [
+            "listing http://shop.test/",
+            "product http://shop.test/products/1",
+            "product http://shop.test/products/2",
+        ]
+    );
+
+    // Unknown callbacks are not worth retrying.
+    assert_eq!(report.pages_visited, 4);
+    assert_eq!(report.scrape_errors, 1);
+    assert_eq!(report.retries, 0);
+    assert_eq!(
+        report.failed_urls["http://shop.test/reviews"],
+        ScrapeFailure {
+            attempts: 1,
+            error: String::from("the spider has no callback `parse_review`"),
+        }
+    );
+}
@@ -0,0 +1,93 @@
+//! Helpers shared by the integration tests. Each test crate uses only some of
+//! them.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{Downloader, Error, ItemError, ItemPipeline, Request, Response, StatusCode};
+
+/// A downloader answering every request with an empty page right away, for
+/// spiders that make their pages up in `scrape`.
+pub struct StubDownloader;
+
+#[async_trait]
+impl Downloader for StubDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        Ok(Response {
+            url: request.url.clone(),
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body: Vec::new(),
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        })
+    }
+}
+
+/// An item pipeline collecting the items it gets.
+#[derive(Clone, Default)]
+pub struct Collect(Arc<Mutex<Vec<String>>>);
+
+impl Collect {
+    /// The items collected so far, in the order they came.
+    pub fn items(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// The items collected so far, sorted.
+    pub fn sorted(&self) -> Vec<String> {
+        let mut items = self.items();
+        items.sort();
+        items
+    }
+}
+
+#[async_trait]
+impl ItemPipeline<String> for Collect {
+    async fn process_item(&self, item: String) -> Result<String, ItemError> {
+        self.0.lock().unwrap().push(item.clone());
+        Ok(item)
+    }
+}
+
+/// A directory in the system's temporary directory, removed when created if
+/// an earlier run left it behind, and removed with its contents when dropped.
+/// The directory itself is left for the code under test to create.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Reserves the directory `scrapy-<name>-<pid>`.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("scrapy-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
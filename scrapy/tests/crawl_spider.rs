@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use scrapy::{
+    CrawlSpider, CrawlerBuilder, Downloader, Error, LinkExtractor, Request, Response, Rule,
+    StatusCode,
+};
+
+use common::Collect;
+
+mod common;
+
+/// A downloader serving a small shop: the home page links to two
+/// categories, each listing a product and linking to a second page.
+struct ShopDownloader;
+
+#[async_trait]
+impl Downloader for ShopDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let path = request.url.trim_start_matches("http://shop.test");
+        let body = match path {
+            "/" => {
+                r#"<nav><a href="/category/books">Books</a><a href="/category/music">Music</a></nav>
+                <a href="/about">About</a>"#
+            }
+            "/category/books" => {
+                r#"<a class="product" href="/product/1">Book</a>
+                <a class="next" href="/category/books?page=2">Next</a>"#
+            }
+            "/category/books?page=2" => r#"<a class="product" href="/product/2">Book 2</a>"#,
+            "/category/music" => {
+                r#"<a class="product" href="/product/3">Album</a>
+                <a class="product" href="/product/1">Book</a>"#
+            }
+            _ => r#"<h1>Product</h1><a class="product" href="/product/9">Related</a>"#,
+        };
+
+        Ok(Response {
+            url: request.url.clone(),
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        })
+    }
+}
+
+/// Scrapes product pages into their URL, and the start page into nothing.
+struct ShopSpider;
+
+#[async_trait]
+impl scrapy::Spider for ShopSpider {
+    type Item = String;
+    type Error = Error;
+
+    fn name(&self) -> String {
+        String::from("shop")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://shop.test/")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<String>, Vec<Request>), Error> {
+        Ok((vec![format!("start {}", response.url)], Vec::new()))
+    }
+
+    async fn parse(
+        &self,
+        callback: &str,
+        response: &Response,
+    ) -> Option<Result<(Vec<String>, Vec<Request>), Error>> {
+        match callback {
+            "parse_product" => Some(Ok((vec![response.url.clone()], Vec::new()))),
+            _ => None,
+        }
+    }
+}
+
+async fn crawl(spider: CrawlSpider<ShopSpider>) -> (Vec<String>, usize) {
+    let collect = Collect::default();
+    let report = CrawlerBuilder::new()
+        .downloader(ShopDownloader)
+        .delay(Duration::ZERO)
+        .item_pipeline(collect.clone())
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    let items = collect.sorted();
+    (items, report.pages_visited)
+}
+
+fn products() -> Rule {
+    Rule::new(LinkExtractor::new().restrict_css(".product")).callback("parse_product")
+}
+
+#[tokio::test]
+async fn rules_follow_listings_and_scrape_products() {
+    let spider = CrawlSpider::new(ShopSpider)
+        .rule(Rule::new(
+            LinkExtractor::new()
+                .restrict_css("nav")
+                .allow("/category/")
+                .unwrap(),
+        ))
+        .rule(Rule::new(LinkExtractor::new().restrict_css(".next")))
+        .rule(products());
+
+    let (items, pages_visited) = crawl(spider).await;
+
+    // Product pages are not followed, so related products are left alone.
+    assert_eq!(
+        items,
+        [
+            "http://shop.test/product/1",
+            "http://shop.test/product/2",
+            "http://shop.test/product/3",
+            "start http://shop.test/",
+        ]
+    );
+    assert_eq!(pages_visited, 7);
+}
+
+#[tokio::test]
+async fn rules_with_a_callback_can_follow_too() {
+    let spider = CrawlSpider::new(ShopSpider)
+        .rule(Rule::new(
+            LinkExtractor::new().allow("/category/music").unwrap(),
+        ))
+        .rule(products().follow(true));
+
+    let (items, _) = crawl(spider).await;
+
+    assert_eq!(
+        items,
+        [
+            "http://shop.test/product/1",
+            "http://shop.test/product/3",
+            "http://shop.test/product/9",
+            "start http://shop.test/",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn invalid_rules_fail_the_page() {
+    let spider =
+        CrawlSpider::new(ShopSpider).rule(Rule::new(LinkExtractor::new().restrict_css("nav[")));
+
+    let (items, pages_visited) = crawl(spider).await;
+
+    // The start page failed, so its item went nowhere.
+    assert!(items.is_empty());
+    assert_eq!(pages_visited, 1);
+}
@@ -0,0 +1,165 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{CrawlerBuilder, Method, Request, Response, RetryPolicy, Spider, StatusCode};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Serves a small site:
+///
+/// * `/` links to the other pages.
+/// * `/redirect` redirects to `/final`.
+/// * `/login` sets a session cookie.
+/// * `/echo` answers with the method, cookie and body it received.
+/// * anything else is a 404.
+async fn serve() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut buf = vec![0; 8192];
+                let len = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..len]).into_owned();
+                let (head, body) = request.split_once("\r\n\r\n").unwrap_or((&request, ""));
+                let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+                let method = request_line.next().unwrap_or_default();
+                let path = request_line.next().unwrap_or_default();
+                let cookie = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("cookie: "))
+                    .unwrap_or("-");
+
+                let (status, headers, body) = match path {
+                    "/" => ("200 OK", "x-site: test\r\n", String::from("index")),
+                    "/redirect" => ("302 Found", "location: /final\r\n", String::new()),
+                    "/final" => ("200 OK", "", String::from("final")),
+                    "/login" => ("200 OK", "set-cookie: session=42\r\n", String::new()),
+                    "/echo" => ("200 OK", "", format!("{} {} {}", method, cookie, body)),
+                    _ => ("404 Not Found", "", String::new()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    headers,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    addr
+}
+
+/// A spider recording every response it gets. The index page links to the
+/// redirect, a missing page and, once logged in, a form post.
+struct SiteSpider {
+    addr: SocketAddr,
+    responses: Arc<Mutex<Vec<Response>>>,
+}
+
+impl SiteSpider {
+    fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            responses: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Spider for SiteSpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("site")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![format!("http://{}/", self.addr)]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        self.responses.lock().unwrap().push(response.clone());
+
+        let links = match response.url.rsplit('/').next().unwrap() {
+            "" => vec!["/redirect".into(), "/missing".into(), "/login".into()],
+            "login" => vec![Request::new("/echo").method(Method::POST).body("q=rust")],
+            _ => Vec::new(),
+        };
+
+        Ok((Vec::new(), links))
+    }
+}
+
+fn builder() -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .delay(Duration::ZERO)
+        .crawling_concurrency(1)
+}
+
+#[tokio::test]
+async fn spiders_receive_downloaded_responses() {
+    let addr = serve().await;
+    let spider = SiteSpider::new(addr);
+    let responses = spider.responses.clone();
+
+    let report = builder().build().crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 5);
+    let response = |path: &str| {
+        let url = format!("http://{}{}", addr, path);
+        responses
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|response| response.url == url)
+            .cloned()
+            .unwrap_or_else(|| panic!("{} was not scraped", url))
+    };
+
+    let index = response("/");
+    assert_eq!(index.status, StatusCode::OK);
+    assert_eq!(index.header("X-Site"), Some("test"));
+    assert_eq!(index.text(), "index");
+
+    let redirected = response("/final");
+    assert!(redirected.request.url.ends_with("/redirect"));
+    assert_eq!(redirected.text(), "final");
+
+    assert_eq!(response("/missing").status, StatusCode::NOT_FOUND);
+
+    // The session cookie set by `/login` is sent with the form post.
+    assert_eq!(response("/echo").text(), "POST session=42 q=rust");
+}
+
+#[tokio::test]
+async fn failed_downloads_are_retried() {
+    // Nothing listens on the port once the listener is dropped.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let spider = SiteSpider::new(addr);
+    let retry = RetryPolicy::new()
+        .max_attempts(2)
+        .backoff(Duration::from_millis(10));
+
+    let report = builder().retry(retry).build().crawl(spider).await.unwrap();
+
+    assert_eq!(report.retries, 1);
+    assert_eq!(report.scrape_errors, 2);
+    let failure = &report.failed_urls[&format!("http://{}/", addr)];
+    assert_eq!(failure.attempts, 2);
+}
@@ -0,0 +1,306 @@
+use std::{collections::BTreeMap, fs, io::Read, path::Path, time::Duration};
+
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use scrapy::{
+    CloseReason, CrawlerBuilder, FeedExporter, FeedFormat, ItemPipeline, Request, Response, Spider,
+};
+use serde::Serialize;
+
+use common::{StubDownloader, TempDir};
+
+mod common;
+
+#[derive(Serialize)]
+struct Quote {
+    text: String,
+    author: String,
+    tags: Vec<String>,
+}
+
+/// A spider whose only page yields `quotes` quotes.
+struct QuotesSpider {
+    quotes: usize,
+}
+
+#[async_trait]
+impl Spider for QuotesSpider {
+    type Item = Quote;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("quotes")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://quotes.test/")]
+    }
+
+    async fn scrape(&self, _response: &Response) -> Result<(Vec<Quote>, Vec<Request>), String> {
+        let quotes = vec![
+            Quote {
+                text: String::from("Less is \"more\", <really>"),
+                author: String::from("A & B"),
+                tags: vec![String::from("a"), String::from("b")],
+            },
+            Quote {
+                text: String::from("Hi"),
+                author: String::from("C"),
+                tags: Vec::new(),
+            },
+        ];
+
+        Ok((quotes.into_iter().take(self.quotes).collect(), Vec::new()))
+    }
+}
+
+/// Crawls `quotes` quotes into `exporter`.
+async fn crawl(exporter: FeedExporter<Quote>, quotes: usize) -> scrapy::CrawlReport {
+    let crawler = CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .processing_concurrency(1)
+        .item_pipeline(exporter)
+        .build();
+
+    crawler.crawl(QuotesSpider { quotes }).await.unwrap()
+}
+
+/// Crawls `quotes` quotes into `exporter` and returns what it wrote.
+async fn export(path: &Path, exporter: FeedExporter<Quote>, quotes: usize) -> String {
+    let report = crawl(exporter, quotes).await;
+    assert_eq!(report.items_processed, quotes);
+
+    fs::read_to_string(path).unwrap()
+}
+
+/// The names of the files in `dir`, sorted.
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn json_lines_feed() {
+    let dir = TempDir::new("feed-quotes-jsonl");
+    let path = dir.join("quotes.jsonl");
+    let exporter = FeedExporter::new(&path, FeedFormat::JsonLines);
+
+    assert_eq!(
+        export(&path, exporter, 2).await,
+        concat!(
+            r#"{"text":"Less is \"more\", <really>","author":"A & B","tags":["a","b"]}"#,
+            "\n",
+            r#"{"text":"Hi","author":"C","tags":[]}"#,
+            "\n",
+        )
+    );
+}
+
+#[tokio::test]
+async fn json_feed() {
+    let dir = TempDir::new("feed-quotes-json");
+    let path = dir.join("quotes.json");
+    let exporter = FeedExporter::new(&path, FeedFormat::Json);
+
+    assert_eq!(
+        export(&path, exporter, 2).await,
+        concat!(
+            "[\n",
+            r#"{"text":"Less is \"more\", <really>","author":"A & B","tags":["a","b"]}"#,
+            ",\n",
+            r#"{"text":"Hi","author":"C","tags":[]}"#,
+            "\n]\n",
+        )
+    );
+}
+
+#[tokio::test]
+async fn empty_json_feed_is_an_empty_array() {
+    let dir = TempDir::new("feed-empty-json");
+    let path = dir.join("empty.json");
+    let exporter = FeedExporter::new(&path, FeedFormat::Json);
+
+    assert_eq!(export(&path, exporter, 0).await, "[]\n");
+}
+
+#[tokio::test]
+async fn csv_feed() {
+    let dir = TempDir::new("feed-quotes-csv");
+    let path = dir.join("quotes.csv");
+    let exporter = FeedExporter::new(&path, FeedFormat::Csv);
+
+    assert_eq!(
+        export(&path, exporter, 2).await,
+        concat!(
+            "text,author,tags\n",
+            r#""Less is ""more"", <really>",A & B,"[""a"",""b""]""#,
+            "\n",
+            "Hi,C,[]\n",
+        )
+    );
+}
+
+#[tokio::test]
+async fn csv_feed_with_field_order() {
+    let dir = TempDir::new("feed-authors-csv");
+    let path = dir.join("authors.csv");
+    let exporter = FeedExporter::new(&path, FeedFormat::Csv).fields(["author", "text"]);
+
+    assert_eq!(
+        export(&path, exporter, 2).await,
+        concat!(
+            "author,text\n",
+            r#"A & B,"Less is ""more"", <really>""#,
+            "\n",
+            "C,Hi\n",
+        )
+    );
+}
+
+#[tokio::test]
+async fn xml_feed() {
+    let dir = TempDir::new("feed-quotes-xml");
+    let path = dir.join("quotes.xml");
+    let exporter = FeedExporter::new(&path, FeedFormat::Xml);
+
+    assert_eq!(
+        export(&path, exporter, 2).await,
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n",
+            "<items>\n",
+            "<item><text>Less is &quot;more&quot;, &lt;really&gt;</text><author>A &amp; B</author>",
+            "<tags><value>a</value><value>b</value></tags></item>\n",
+            "<item><text>Hi</text><author>C</author><tags></tags></item>\n",
+            "</items>\n",
+        )
+    );
+}
+
+#[tokio::test]
+async fn xml_fields_that_are_not_element_names_are_named_by_attribute() {
+    let dir = TempDir::new("feed-fields-xml");
+    let path = dir.join("fields.xml");
+    let exporter = FeedExporter::new(&path, FeedFormat::Xml);
+
+    let item = BTreeMap::from([
+        ("1st".to_string(), "a".to_string()),
+        ("first name".to_string(), "b".to_string()),
+        ("q\"<&>".to_string(), "c".to_string()),
+        ("ns:tag".to_string(), "d".to_string()),
+        ("été-1.x".to_string(), "e".to_string()),
+    ]);
+    exporter.open("fields").await.unwrap();
+    exporter.process_item(item).await.unwrap();
+    exporter.close().await.unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n",
+            "<items>\n",
+            "<item><field name=\"1st\">a</field><field name=\"first name\">b</field>",
+            "<field name=\"ns:tag\">d</field><field name=\"q&quot;&lt;&amp;&gt;\">c</field>",
+            "<été-1.x>e</été-1.x></item>\n",
+            "</items>\n",
+        )
+    );
+}
+
+#[tokio::test]
+async fn batches_rotate_by_item_count() {
+    let dir = TempDir::new("feed-batches");
+    let exporter = FeedExporter::new(dir.join("{spider}/{batch}.jsonl"), FeedFormat::JsonLines)
+        .fields(["author"])
+        .batch_items(1);
+
+    let report = crawl(exporter, 2).await;
+    assert_eq!(report.items_processed, 2);
+
+    let dir = dir.join("quotes");
+    assert_eq!(file_names(&dir), ["1.jsonl", "2.jsonl"]);
+    assert_eq!(
+        fs::read_to_string(dir.join("1.jsonl")).unwrap(),
+        "{\"author\":\"A & B\"}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("2.jsonl")).unwrap(),
+        "{\"author\":\"C\"}\n"
+    );
+}
+
+#[tokio::test]
+async fn batches_rotate_by_size() {
+    let dir = TempDir::new("feed-sized");
+    let exporter = FeedExporter::new(dir.join("{batch}.json"), FeedFormat::Json).batch_size(1);
+
+    crawl(exporter, 2).await;
+
+    // Every item fills a batch, and no file is left partial.
+    assert_eq!(file_names(&dir), ["1.json", "2.json"]);
+    assert_eq!(
+        fs::read_to_string(dir.join("2.json")).unwrap(),
+        "[\n{\"text\":\"Hi\",\"author\":\"C\",\"tags\":[]}\n]\n"
+    );
+}
+
+#[tokio::test]
+async fn gzip_feed_with_time_in_its_path() {
+    let dir = TempDir::new("feed-gzip");
+    let exporter = FeedExporter::new(dir.join("{spider}-{time}.csv.gz"), FeedFormat::Csv)
+        .fields(["author"])
+        .gzip(true);
+
+    crawl(exporter, 2).await;
+
+    let names = file_names(&dir);
+    assert_eq!(names.len(), 1);
+    assert!(names[0].starts_with("quotes-20"), "{}", names[0]);
+    assert!(names[0].ends_with(".csv.gz"), "{}", names[0]);
+
+    let mut csv = String::new();
+    GzDecoder::new(fs::File::open(dir.join(&names[0])).unwrap())
+        .read_to_string(&mut csv)
+        .unwrap();
+    assert_eq!(csv, "author\nA & B\nC\n");
+}
+
+#[tokio::test]
+async fn batched_feed_needs_a_batch_placeholder() {
+    let dir = TempDir::new("feed-unbatched");
+    let exporter =
+        FeedExporter::new(dir.join("quotes.jsonl"), FeedFormat::JsonLines).batch_items(1);
+
+    let report = crawl(exporter, 2).await;
+    assert_eq!(report.close_reason, CloseReason::PipelineFailed);
+    assert!(!dir.exists());
+}
+
+#[test]
+fn format_is_inferred_from_the_extension() {
+    assert_eq!(
+        FeedFormat::from_path("items.jsonl"),
+        Some(FeedFormat::JsonLines)
+    );
+    assert_eq!(
+        FeedFormat::from_path("items.jl"),
+        Some(FeedFormat::JsonLines)
+    );
+    assert_eq!(
+        FeedFormat::from_path("out/items.JSON"),
+        Some(FeedFormat::Json)
+    );
+    assert_eq!(FeedFormat::from_path("items.csv"), Some(FeedFormat::Csv));
+    assert_eq!(FeedFormat::from_path("items.xml"), Some(FeedFormat::Xml));
+    assert_eq!(
+        FeedFormat::from_path("items.jsonl.gz"),
+        Some(FeedFormat::JsonLines)
+    );
+    assert_eq!(FeedFormat::from_path("items.txt"), None);
+    assert_eq!(FeedFormat::from_path("items.gz"), None);
+    assert_eq!(FeedFormat::from_path("items"), None);
+}
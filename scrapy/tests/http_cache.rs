@@ -0,0 +1,244 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{
+    CachePolicy, CrawlerBuilder, Downloader, DownloaderMiddleware, Error, HttpCache, Request,
+    Response, RetryPolicy, ScrapeFailure, Spider, StatusCode,
+};
+
+use common::{Collect, TempDir};
+
+mod common;
+
+/// A spider scraping the body of each of its start pages.
+struct PagesSpider(Vec<&'static str>);
+
+#[async_trait]
+impl Spider for PagesSpider {
+    type Item = String;
+    type Error = Error;
+
+    fn name(&self) -> String {
+        String::from("pages")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        self.0.iter().map(|url| url.to_string()).collect()
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<String>, Vec<Request>), Error> {
+        Ok((vec![response.text().into_owned()], Vec::new()))
+    }
+}
+
+/// A site whose pages are sent with the headers of their path, counting the
+/// requests it gets. Pages with an `ETag` are answered `304 Not Modified` to
+/// requests that have it already.
+#[derive(Clone, Default)]
+struct Site {
+    requests: Arc<Mutex<Vec<Request>>>,
+    version: Arc<Mutex<usize>>,
+}
+
+impl Site {
+    fn requests(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Changes the body of every page.
+    fn update(&self) {
+        *self.version.lock().unwrap() += 1;
+    }
+}
+
+#[async_trait]
+impl Downloader for Site {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        self.requests.lock().unwrap().push(request.clone());
+
+        let path = request.url.trim_start_matches("http://site.test");
+        let headers: Vec<(&str, &str)> = match path {
+            "/fresh" => vec![("Cache-Control", "max-age=3600")],
+            "/private" => vec![("Cache-Control", "no-store")],
+            "/expired" => vec![
+                ("Date", "Tue, 14 Nov 2023 08:00:00 GMT"),
+                ("Expires", "Tue, 14 Nov 2023 07:00:00 GMT"),
+            ],
+            "/tagged" => vec![("Cache-Control", "no-cache"), ("ETag", "\"v1\"")],
+            _ => Vec::new(),
+        };
+
+        let etag = request
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("if-none-match"));
+        if path == "/tagged" && etag.is_some() {
+            return Ok(Response {
+                url: request.url.clone(),
+                status: StatusCode::NOT_MODIFIED,
+                headers: vec![("ETag".into(), "\"v1\"".into())],
+                body: Vec::new(),
+                elapsed: Duration::ZERO,
+                request: request.clone(),
+            });
+        }
+
+        let version = *self.version.lock().unwrap();
+        Ok(Response {
+            url: request.url.clone(),
+            status: StatusCode::OK,
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: format!("{} v{}", path, version).into_bytes(),
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        })
+    }
+}
+
+/// Crawls `urls` through `cache`, returning the scraped page bodies.
+async fn crawl(site: &Site, cache: HttpCache, urls: Vec<&'static str>) -> Vec<String> {
+    let collect = Collect::default();
+    let crawler = CrawlerBuilder::new()
+        .downloader(site.clone())
+        .downloader_middleware(cache)
+        .delay(Duration::ZERO)
+        .item_pipeline(collect.clone())
+        .build();
+
+    crawler.crawl(PagesSpider(urls)).await.unwrap();
+
+    collect.sorted()
+}
+
+#[tokio::test]
+async fn cached_pages_are_not_downloaded_again() {
+    let dir = TempDir::new("cache-always");
+    let site = Site::default();
+    let urls = vec!["http://site.test/a", "http://site.test/b"];
+
+    let items = crawl(&site, HttpCache::new(&dir), urls.clone()).await;
+    assert_eq!(items, ["/a v0", "/b v0"]);
+
+    site.update();
+    let items = crawl(&site, HttpCache::new(&dir), urls).await;
+    assert_eq!(items, ["/a v0", "/b v0"]);
+    assert_eq!(site.requests(), 2);
+}
+
+#[tokio::test]
+async fn cached_pages_expire_after_max_age() {
+    let dir = TempDir::new("cache-max-age");
+    let site = Site::default();
+    let urls = vec!["http://site.test/a"];
+
+    crawl(&site, HttpCache::new(&dir), urls.clone()).await;
+    site.update();
+
+    let day = CachePolicy::MaxAge(Duration::from_secs(24 * 3600));
+    let items = crawl(&site, HttpCache::new(&dir).policy(day), urls.clone()).await;
+    assert_eq!(items, ["/a v0"]);
+    assert_eq!(site.requests(), 1);
+
+    let expired = CachePolicy::MaxAge(Duration::ZERO);
+    let items = crawl(&site, HttpCache::new(&dir).policy(expired), urls).await;
+    assert_eq!(items, ["/a v1"]);
+    assert_eq!(site.requests(), 2);
+}
+
+#[tokio::test]
+async fn rfc9111_policy_follows_response_headers() {
+    let dir = TempDir::new("cache-rfc9111");
+    let site = Site::default();
+    let urls = vec![
+        "http://site.test/fresh",
+        "http://site.test/private",
+        "http://site.test/expired",
+        "http://site.test/plain",
+    ];
+    let cache = || HttpCache::new(&dir).policy(CachePolicy::Rfc9111);
+
+    crawl(&site, cache(), urls.clone()).await;
+    site.update();
+
+    // Only the page with a max-age is still fresh.
+    let items = crawl(&site, cache(), urls).await;
+    assert_eq!(
+        items,
+        ["/expired v1", "/fresh v0", "/plain v1", "/private v1"]
+    );
+    assert_eq!(site.requests(), 7);
+}
+
+#[tokio::test]
+async fn stale_pages_are_revalidated() {
+    let dir = TempDir::new("cache-revalidate");
+    let site = Site::default();
+    let urls = vec!["http://site.test/tagged"];
+    let cache = || HttpCache::new(&dir).policy(CachePolicy::Rfc9111);
+
+    crawl(&site, cache(), urls.clone()).await;
+    site.update();
+
+    let items = crawl(&site, cache(), urls).await;
+    assert_eq!(items, ["/tagged v0"]);
+
+    let requests = site.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1]
+        .headers
+        .contains(&(String::from("If-None-Match"), String::from("\"v1\""))));
+    drop(requests);
+}
+
+#[tokio::test]
+async fn responses_from_the_cache_are_flagged() {
+    let dir = TempDir::new("cache-flagged");
+    let cache = HttpCache::new(&dir);
+    let mut request = Request::new("http://site.test/a");
+
+    assert!(cache.process_request(&mut request).await.unwrap().is_none());
+    let downloaded = Site::default().fetch(&request).await.unwrap();
+    let downloaded = cache.process_response(downloaded).await.unwrap();
+    assert!(!downloaded.request.meta.contains_key("cached"));
+
+    let cached = cache.process_request(&mut request).await.unwrap().unwrap();
+    assert_eq!(cached.request.meta["cached"], true);
+    let cached = cache.process_response(cached).await.unwrap();
+    assert_eq!(cached.body, downloaded.body);
+}
+
+#[tokio::test]
+async fn offline_crawls_fail_fast_on_missing_pages() {
+    let dir = TempDir::new("cache-offline");
+    let site = Site::default();
+
+    crawl(&site, HttpCache::new(&dir), vec!["http://site.test/a"]).await;
+
+    let collect = Collect::default();
+    let crawler = CrawlerBuilder::new()
+        .downloader(site.clone())
+        .downloader_middleware(HttpCache::new(&dir).offline(true))
+        .delay(Duration::ZERO)
+        .retry(RetryPolicy::new().max_attempts(3).jitter(false))
+        .item_pipeline(collect.clone())
+        .build();
+    let spider = PagesSpider(vec!["http://site.test/a", "http://site.test/b"]);
+    let report = crawler.crawl(spider).await.unwrap();
+
+    assert_eq!(collect.items(), ["/a v0"]);
+    assert_eq!(site.requests(), 1);
+    assert_eq!(report.retries, 0);
+    assert_eq!(
+        report.failed_urls["http://site.test/b"],
+        ScrapeFailure {
+            attempts: 1,
+            error: String::from("Not in the HTTP cache: http://site.test/b"),
+        }
+    );
+}
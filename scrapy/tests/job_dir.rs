@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{CloseReason, CrawlerBuilder, Error, Request, Response, Spider};
+
+use common::{StubDownloader, TempDir};
+
+mod common;
+
+const PAGES: usize = 200;
+
+/// A spider over a binary tree of pages that records every page it fetches.
+#[derive(Clone, Default)]
+struct TreeSpider {
+    fetches: Arc<Mutex<HashMap<usize, usize>>>,
+}
+
+impl TreeSpider {
+    fn fetch_counts(&self) -> HashMap<usize, usize> {
+        self.fetches.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Spider for TreeSpider {
+    type Item = usize;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("tree")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://pages.test/0")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<usize>, Vec<Request>), String> {
+        let url = response.url.as_str();
+        let page: usize = url.rsplit('/').next().unwrap().parse().unwrap();
+        *self.fetches.lock().unwrap().entry(page).or_default() += 1;
+        tokio::time::sleep(Duration::from_millis(2)).await;
+
+        let urls = [2 * page + 1, 2 * page + 2]
+            .into_iter()
+            .filter(|child| *child < PAGES)
+            .map(|child| format!("http://pages.test/{}", child).into())
+            .collect();
+
+        Ok((vec![page], urls))
+    }
+}
+
+fn crawler_builder(path: &Path) -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(4)
+        .crawling_queue_capacity(4)
+        .job_dir(path)
+        .persist_interval(Duration::from_millis(5))
+}
+
+#[tokio::test]
+async fn stopped_crawl_resumes_without_refetching() {
+    let path = TempDir::new("stopped");
+    let first = TreeSpider::default();
+    let crawler = crawler_builder(&path).build();
+    let handle = crawler.handle();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        handle.stop();
+    });
+
+    let report = crawler.crawl(first.clone()).await.unwrap();
+    assert_eq!(report.close_reason, CloseReason::Cancelled);
+
+    let second = TreeSpider::default();
+    let report = crawler_builder(&path)
+        .build()
+        .crawl(second.clone())
+        .await
+        .unwrap();
+    assert_eq!(report.close_reason, CloseReason::Finished);
+
+    let first = first.fetch_counts();
+    let second = second.fetch_counts();
+
+    for page in 0..PAGES {
+        let fetches = first.get(&page).unwrap_or(&0) + second.get(&page).unwrap_or(&0);
+        assert_eq!(fetches, 1, "page {} fetched {} times", page, fetches);
+    }
+
+    // A finished job has nothing left to do.
+    let third = TreeSpider::default();
+    crawler_builder(&path)
+        .build()
+        .crawl(third.clone())
+        .await
+        .unwrap();
+    assert!(third.fetch_counts().is_empty());
+}
+
+#[test]
+fn killed_crawl_resumes_without_skipping() {
+    let path = TempDir::new("killed");
+    let first = TreeSpider::default();
+
+    // Shutting the runtime down drops every task mid-flight, like a kill.
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.spawn({
+        let path = path.to_path_buf();
+        let first = first.clone();
+        async move { crawler_builder(&path).build().crawl(first).await.unwrap() }
+    });
+    std::thread::sleep(Duration::from_millis(60));
+    runtime.shutdown_background();
+
+    let first = first.fetch_counts();
+    assert!(
+        first.len() < PAGES,
+        "the crawl finished before it was killed"
+    );
+
+    let second = TreeSpider::default();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime
+        .block_on(crawler_builder(&path).build().crawl(second.clone()))
+        .unwrap();
+    let second = second.fetch_counts();
+
+    let mut refetched = 0;
+    for page in 0..PAGES {
+        let fetches = first.get(&page).unwrap_or(&0) + second.get(&page).unwrap_or(&0);
+        assert!(fetches > 0, "page {} was skipped", page);
+        refetched += fetches - 1;
+    }
+
+    // Only pages that were in flight when the crawl was killed run again.
+    assert!(refetched <= 4, "{} pages were fetched again", refetched);
+}
+
+#[tokio::test]
+async fn unwritable_job_dir_fails_the_crawl() {
+    let path = TempDir::new("unwritable");
+    let file = path.join("file");
+    std::fs::create_dir_all(&path).unwrap();
+    std::fs::write(&file, "").unwrap();
+
+    let spider = TreeSpider::default();
+    let result = crawler_builder(&file.join("job"))
+        .build()
+        .crawl(spider.clone())
+        .await;
+
+    assert!(matches!(result, Err(Error::Io(_))));
+    assert!(spider.fetch_counts().is_empty());
+}
@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use scrapy::{CloseReason, CrawlerBuilder, Request, Response, RetryPolicy, Spider};
+
+use common::StubDownloader;
+
+mod common;
+
+/// A spider over an infinite binary tree where page `n` links to `2n + 1` and
+/// `2n + 2`, yields one item, and fails if `n % fail_every == 1`.
+struct TreeSpider {
+    fail_every: Option<usize>,
+    latency: Duration,
+}
+
+impl TreeSpider {
+    fn new() -> Self {
+        Self {
+            fail_every: None,
+            latency: Duration::ZERO,
+        }
+    }
+}
+
+#[async_trait]
+impl Spider for TreeSpider {
+    type Item = usize;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("tree")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://tree.test/0")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<usize>, Vec<Request>), String> {
+        let url = response.url.as_str();
+        let page: usize = url.rsplit('/').next().unwrap().parse().unwrap();
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+
+        if self.fail_every.is_some_and(|every| page % every == 1) {
+            return Err(format!("page {} failed", page));
+        }
+
+        let links = [2 * page + 1, 2 * page + 2]
+            .iter()
+            .map(|child| format!("http://tree.test/{}", child).into())
+            .collect();
+
+        Ok((vec![page], links))
+    }
+}
+
+fn builder() -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(4)
+}
+
+#[tokio::test]
+async fn max_depth_bounds_the_tree() {
+    let report = builder()
+        .max_depth(3)
+        .build()
+        .crawl(TreeSpider::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 15);
+    assert_eq!(report.close_reason, CloseReason::Finished);
+}
+
+#[tokio::test]
+async fn max_pages_closes_the_crawl() {
+    let report = builder()
+        .max_pages(50)
+        .build()
+        .crawl(TreeSpider::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 50);
+    assert_eq!(report.close_reason, CloseReason::MaxPages);
+}
+
+#[tokio::test]
+async fn max_pages_of_zero_closes_the_crawl_right_away() {
+    let report = builder()
+        .max_pages(0)
+        .timeout(Duration::from_secs(3))
+        .build()
+        .crawl(TreeSpider::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 0);
+    assert_eq!(report.close_reason, CloseReason::MaxPages);
+}
+
+#[tokio::test]
+async fn retries_do_not_count_towards_max_pages() {
+    let retry = RetryPolicy::new()
+        .max_attempts(3)
+        .backoff(Duration::from_millis(5))
+        .jitter(false);
+    let spider = TreeSpider {
+        fail_every: Some(3),
+        latency: Duration::ZERO,
+    };
+    let report = builder()
+        .retry(retry)
+        .max_pages(20)
+        .timeout(Duration::from_secs(10))
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    assert_eq!(report.close_reason, CloseReason::MaxPages);
+    assert!(!report.failed_urls.is_empty());
+    assert_eq!(report.retries, 2 * report.failed_urls.len());
+    assert_eq!(report.pages_visited, 20);
+    assert_eq!(report.items_scraped + report.failed_urls.len(), 20);
+}
+
+#[tokio::test]
+async fn max_items_closes_the_crawl() {
+    let report = builder()
+        .max_items(30)
+        .build()
+        .crawl(TreeSpider::new())
+        .await
+        .unwrap();
+
+    assert!(report.items_processed >= 30);
+    assert_eq!(report.close_reason, CloseReason::MaxItems);
+}
+
+#[tokio::test]
+async fn max_errors_closes_the_crawl() {
+    let spider = TreeSpider {
+        fail_every: Some(3),
+        ..TreeSpider::new()
+    };
+    let report = builder().max_errors(5).build().crawl(spider).await.unwrap();
+
+    assert!(report.scrape_errors >= 5);
+    assert_eq!(report.close_reason, CloseReason::MaxErrors);
+}
+
+#[tokio::test]
+async fn timeout_closes_the_crawl() {
+    let spider = TreeSpider {
+        latency: Duration::from_millis(10),
+        ..TreeSpider::new()
+    };
+    let report = builder()
+        .timeout(Duration::from_millis(200))
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    assert!(report.duration >= Duration::from_millis(200));
+    assert!(report.duration < Duration::from_secs(5));
+    assert_eq!(report.close_reason, CloseReason::Timeout);
+}
+
+#[tokio::test]
+async fn crawl_within_its_limits_finishes() {
+    let report = builder()
+        .max_depth(2)
+        .max_pages(100)
+        .build()
+        .crawl(TreeSpider::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 7);
+    assert_eq!(report.close_reason, CloseReason::Finished);
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use scrapy::{LinkExtractor, Request, Response, SelectorError, StatusCode};
+
+const PAGE: &str = r#"<html><body>
+  <nav>
+    <a href="/page/2/?b=2&a=1#top">Next</a>
+    <a href="https://quotes.test/page/3/">Last</a>
+  </nav>
+  <div class="quote">
+    <a href="/author/ada">Ada</a>
+    <a href="/author/ada#bio">Ada again</a>
+    <a href="/login" rel="nofollow noopener">Log in</a>
+    <a href="mailto:ada@quotes.test">Mail</a>
+    <a href="https://other.test/">Elsewhere</a>
+    <a href="https://cdn.quotes.test/image.png">Image</a>
+    <map><area href="/area" /></map>
+  </div>
+  <link rel="alternate" href="/feed.xml" />
+  <iframe src="/embedded"></iframe>
+</body></html>"#;
+
+fn response(html: &str) -> Response {
+    Response {
+        url: String::from("https://quotes.test/page/1/"),
+        status: StatusCode::OK,
+        headers: Vec::new(),
+        body: html.as_bytes().to_vec(),
+        elapsed: Duration::ZERO,
+        request: Request::new("https://quotes.test/page/1/"),
+    }
+}
+
+fn urls(requests: Vec<Request>) -> Vec<String> {
+    requests.into_iter().map(|request| request.url).collect()
+}
+
+#[test]
+fn links_are_resolved_and_deduplicated() {
+    let links = LinkExtractor::new().extract(&response(PAGE)).unwrap();
+
+    assert_eq!(
+        urls(links),
+        [
+            "https://quotes.test/page/2/?b=2&a=1",
+            "https://quotes.test/page/3/",
+            "https://quotes.test/author/ada",
+            "https://other.test/",
+            "https://cdn.quotes.test/image.png",
+            "https://quotes.test/area",
+        ]
+    );
+}
+
+#[test]
+fn links_are_filtered() {
+    let extractor = LinkExtractor::new()
+        .allow(r"/page/\d+/")
+        .unwrap()
+        .allow("/author/")
+        .unwrap()
+        .deny(r"/page/3/")
+        .unwrap()
+        .allow_domains(["quotes.test"]);
+
+    assert_eq!(
+        urls(extractor.extract(&response(PAGE)).unwrap()),
+        [
+            "https://quotes.test/page/2/?b=2&a=1",
+            "https://quotes.test/author/ada",
+        ]
+    );
+
+    let subdomains = LinkExtractor::new().allow_domains(["QUOTES.test"]);
+    assert_eq!(
+        urls(subdomains.extract(&response(PAGE)).unwrap()).len(),
+        5,
+        "subdomains of allowed domains are allowed"
+    );
+}
+
+#[test]
+fn links_are_restricted_to_regions_and_tags() {
+    let nav = LinkExtractor::new().restrict_css("nav");
+    assert_eq!(
+        urls(nav.extract(&response(PAGE)).unwrap()),
+        [
+            "https://quotes.test/page/2/?b=2&a=1",
+            "https://quotes.test/page/3/",
+        ]
+    );
+
+    let embedded = LinkExtractor::new()
+        .restrict_css("body > link")
+        .restrict_css("body")
+        .link_tags(true)
+        .iframes(true)
+        .allow("feed|embedded")
+        .unwrap();
+    assert_eq!(
+        urls(embedded.extract(&response(PAGE)).unwrap()),
+        [
+            "https://quotes.test/feed.xml",
+            "https://quotes.test/embedded",
+        ]
+    );
+}
+
+#[test]
+fn links_resolve_against_the_base_url() {
+    let page = r#"<html><head><base href="https://mirror.test/docs/"></head>
+        <body><a href="intro.html">Intro</a></body></html>"#;
+
+    assert_eq!(
+        urls(LinkExtractor::new().extract(&response(page)).unwrap()),
+        ["https://mirror.test/docs/intro.html"]
+    );
+}
+
+#[test]
+fn invalid_rules_are_errors() {
+    let page = response(PAGE);
+
+    assert!(matches!(
+        LinkExtractor::new().allow("("),
+        Err(SelectorError::Regex(_))
+    ));
+    assert!(matches!(
+        LinkExtractor::new().deny("[a-"),
+        Err(SelectorError::Regex(_))
+    ));
+    assert!(matches!(
+        LinkExtractor::new().restrict_css("nav[").extract(&page),
+        Err(SelectorError::Css { .. })
+    ));
+}
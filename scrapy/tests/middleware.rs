@@ -0,0 +1,316 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{
+    CrawlerBuilder, Downloader, DownloaderMiddleware, Error, Request, Response, RetryPolicy,
+    Spider, StatusCode,
+};
+
+use common::StubDownloader;
+
+mod common;
+
+/// A spider whose index page links to `/1`, `/2` and `/cached`, recording
+/// every response it gets.
+#[derive(Default)]
+struct PagesSpider {
+    responses: Arc<Mutex<Vec<Response>>>,
+}
+
+impl PagesSpider {
+    fn response(responses: &Mutex<Vec<Response>>, path: &str) -> Response {
+        let url = format!("http://pages.test{}", path);
+        responses
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|response| response.url == url)
+            .cloned()
+            .unwrap_or_else(|| panic!("{} was not scraped", url))
+    }
+}
+
+#[async_trait]
+impl Spider for PagesSpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("pages")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://pages.test/")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        self.responses.lock().unwrap().push(response.clone());
+
+        let links = if response.url.ends_with('/') {
+            vec!["/1".into(), "/2".into(), "/cached".into()]
+        } else {
+            Vec::new()
+        };
+
+        Ok((Vec::new(), links))
+    }
+}
+
+/// A middleware recording the hooks called on it, in order, across all
+/// middlewares sharing `log`.
+struct Recorder {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl DownloaderMiddleware for Recorder {
+    async fn process_request(&self, request: &mut Request) -> Result<Option<Response>, Error> {
+        let path = request.url.rsplit('/').next().unwrap();
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} request /{}", self.name, path));
+        Ok(None)
+    }
+
+    async fn process_response(&self, response: Response) -> Result<Response, Error> {
+        let path = response.url.rsplit('/').next().unwrap();
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} response /{}", self.name, path));
+        Ok(response)
+    }
+}
+
+/// A middleware adding an authorization header to every request.
+struct Auth;
+
+#[async_trait]
+impl DownloaderMiddleware for Auth {
+    async fn process_request(&self, request: &mut Request) -> Result<Option<Response>, Error> {
+        request
+            .headers
+            .push((String::from("authorization"), String::from("Bearer token")));
+        Ok(None)
+    }
+}
+
+/// A middleware answering requests for `/cached` itself.
+struct Cache;
+
+#[async_trait]
+impl DownloaderMiddleware for Cache {
+    async fn process_request(&self, request: &mut Request) -> Result<Option<Response>, Error> {
+        if !request.url.ends_with("/cached") {
+            return Ok(None);
+        }
+
+        Ok(Some(Response {
+            url: request.url.clone(),
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body: b"from cache".to_vec(),
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        }))
+    }
+}
+
+/// A middleware failing downloads that got a `429 Too Many Requests`, so
+/// they are retried.
+struct RetryTooManyRequests;
+
+#[async_trait]
+impl DownloaderMiddleware for RetryTooManyRequests {
+    async fn process_response(&self, response: Response) -> Result<Response, Error> {
+        if response.status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(Error::Download(
+                format!("{} for {}", response.status, response.url).into(),
+            ));
+        }
+
+        Ok(response)
+    }
+}
+
+/// A middleware answering failed downloads with an empty `503` page.
+struct Fallback;
+
+#[async_trait]
+impl DownloaderMiddleware for Fallback {
+    async fn process_error(&self, request: &Request, _error: Error) -> Result<Response, Error> {
+        Ok(Response {
+            url: request.url.clone(),
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            headers: Vec::new(),
+            body: Vec::new(),
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        })
+    }
+}
+
+/// A downloader answering the first request for every URL with a
+/// `429 Too Many Requests`.
+#[derive(Default)]
+struct ThrottledDownloader {
+    fetched: Mutex<HashMap<String, usize>>,
+}
+
+#[async_trait]
+impl Downloader for ThrottledDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let fetched = {
+            let mut fetched = self.fetched.lock().unwrap();
+            let fetched = fetched.entry(request.url.clone()).or_default();
+            *fetched += 1;
+            *fetched
+        };
+        let mut response = StubDownloader.fetch(request).await?;
+        if fetched == 1 {
+            response.status = StatusCode::TOO_MANY_REQUESTS;
+        }
+
+        Ok(response)
+    }
+}
+
+/// A downloader failing every request.
+struct FailingDownloader;
+
+#[async_trait]
+impl Downloader for FailingDownloader {
+    async fn fetch(&self, _request: &Request) -> Result<Response, Error> {
+        Err(Error::Download("connection refused".into()))
+    }
+}
+
+fn builder() -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(1)
+}
+
+#[tokio::test]
+async fn middlewares_wrap_downloads_in_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let recorder = |name| Recorder {
+        name,
+        log: log.clone(),
+    };
+    let spider = PagesSpider::default();
+    let crawler = builder()
+        .downloader_middleware(recorder("a"))
+        .downloader_middleware(recorder("b"))
+        .build();
+
+    crawler.crawl(spider).await.unwrap();
+
+    let log = log.lock().unwrap();
+    assert_eq!(
+        log[..4],
+        ["a request /", "b request /", "b response /", "a response /"]
+    );
+}
+
+#[tokio::test]
+async fn middlewares_can_change_requests() {
+    let spider = PagesSpider::default();
+    let responses = spider.responses.clone();
+    let crawler = builder().downloader_middleware(Auth).build();
+
+    let report = crawler.crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 4);
+    for response in responses.lock().unwrap().iter() {
+        assert!(response
+            .request
+            .headers
+            .contains(&(String::from("authorization"), String::from("Bearer token"))));
+    }
+}
+
+#[tokio::test]
+async fn middlewares_can_answer_requests_themselves() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let recorder = |name| Recorder {
+        name,
+        log: log.clone(),
+    };
+    let spider = PagesSpider::default();
+    let responses = spider.responses.clone();
+    let crawler = builder()
+        .downloader_middleware(recorder("a"))
+        .downloader_middleware(Cache)
+        .downloader_middleware(recorder("b"))
+        .build();
+
+    let report = crawler.crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 4);
+    assert_eq!(
+        PagesSpider::response(&responses, "/cached").text(),
+        "from cache"
+    );
+
+    // The cached page never reaches the middlewares after the cache, but its
+    // response still goes through all of them.
+    let log = log.lock().unwrap();
+    let cached = log
+        .iter()
+        .filter(|entry| entry.ends_with("/cached"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        cached,
+        [
+            "a request /cached",
+            "b response /cached",
+            "a response /cached"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn failing_a_response_retries_it() {
+    let spider = PagesSpider::default();
+    let responses = spider.responses.clone();
+    let crawler = builder()
+        .downloader(ThrottledDownloader::default())
+        .downloader_middleware(RetryTooManyRequests)
+        .retry(RetryPolicy::new().backoff(Duration::from_millis(10)))
+        .build();
+
+    let report = crawler.crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 4);
+    assert_eq!(report.retries, 4);
+    assert!(report.failed_urls.is_empty());
+    for response in responses.lock().unwrap().iter() {
+        assert_eq!(response.status, StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn middlewares_can_recover_from_errors() {
+    let spider = PagesSpider::default();
+    let responses = spider.responses.clone();
+    let crawler = builder()
+        .downloader(FailingDownloader)
+        .downloader_middleware(Fallback)
+        .build();
+
+    let report = crawler.crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 4);
+    assert_eq!(report.scrape_errors, 0);
+    for response in responses.lock().unwrap().iter() {
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{
+    CloseReason, CrawlerBuilder, Error, ItemError, ItemPipeline, Request, Response, Spider,
+    StatsCollector,
+};
+
+use common::StubDownloader;
+
+mod common;
+
+/// A spider whose only page yields the items `0` to `count - 1`.
+struct NumbersSpider {
+    name: &'static str,
+    count: u32,
+}
+
+#[async_trait]
+impl Spider for NumbersSpider {
+    type Item = u32;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from(self.name)
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![format!("http://{}.test/", self.name)]
+    }
+
+    async fn scrape(&self, _response: &Response) -> Result<(Vec<u32>, Vec<Request>), String> {
+        Ok(((0..self.count).collect(), Vec::new()))
+    }
+}
+
+/// Doubles every item.
+struct Double;
+
+#[async_trait]
+impl ItemPipeline<u32> for Double {
+    async fn process_item(&self, item: u32) -> Result<u32, ItemError> {
+        Ok(item * 2)
+    }
+}
+
+/// Drops the items divisible by 4.
+struct DropMultiplesOfFour;
+
+#[async_trait]
+impl ItemPipeline<u32> for DropMultiplesOfFour {
+    async fn process_item(&self, item: u32) -> Result<u32, ItemError> {
+        if item.is_multiple_of(4) {
+            return Err(ItemError::Dropped(format!("{} is a multiple of 4", item)));
+        }
+
+        Ok(item)
+    }
+}
+
+/// Fails the item `10`.
+struct FailOnTen;
+
+#[async_trait]
+impl ItemPipeline<u32> for FailOnTen {
+    async fn process_item(&self, item: u32) -> Result<u32, ItemError> {
+        if item == 10 {
+            return Err(ItemError::Failed("cannot store 10".into()));
+        }
+
+        Ok(item)
+    }
+}
+
+/// Collects the items it gets, and records when it is opened and closed.
+#[derive(Clone, Default)]
+struct Collect {
+    items: Arc<Mutex<Vec<u32>>>,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Collect {
+    fn items(&self) -> Vec<u32> {
+        let mut items = self.items.lock().unwrap().clone();
+        items.sort();
+        items
+    }
+
+    fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ItemPipeline<u32> for Collect {
+    async fn open(&self, spider: &str) -> Result<(), Error> {
+        self.events.lock().unwrap().push(format!("open {}", spider));
+        Ok(())
+    }
+
+    async fn process_item(&self, item: u32) -> Result<u32, ItemError> {
+        self.items.lock().unwrap().push(item);
+        Ok(item)
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        let collected = self.items.lock().unwrap().len();
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("close after {} items", collected));
+        Ok(())
+    }
+}
+
+/// A pipeline that cannot be opened.
+struct Unavailable;
+
+#[async_trait]
+impl ItemPipeline<u32> for Unavailable {
+    async fn open(&self, _spider: &str) -> Result<(), Error> {
+        Err(Error::Pipeline("database is down".into()))
+    }
+
+    async fn process_item(&self, item: u32) -> Result<u32, ItemError> {
+        Ok(item)
+    }
+}
+
+/// A pipeline for items of another type than the spiders'.
+struct Titles;
+
+#[async_trait]
+impl ItemPipeline<String> for Titles {
+    async fn process_item(&self, item: String) -> Result<String, ItemError> {
+        Ok(item)
+    }
+}
+
+fn builder() -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+}
+
+#[tokio::test]
+async fn items_go_through_every_stage_in_order() {
+    let collect = Collect::default();
+    let crawler = builder()
+        .item_pipeline(Double)
+        .item_pipeline(DropMultiplesOfFour)
+        .item_pipeline(FailOnTen)
+        .item_pipeline(collect.clone())
+        .build();
+
+    let report = crawler
+        .crawl(NumbersSpider {
+            name: "numbers",
+            count: 10,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(collect.items(), [2, 6, 14, 18]);
+    assert_eq!(report.items_scraped, 10);
+    assert_eq!(report.items_processed, 4);
+    assert_eq!(report.items_dropped, 5);
+    assert_eq!(report.process_errors, 1);
+}
+
+#[tokio::test]
+async fn pipelines_are_opened_and_closed_around_each_crawl() {
+    let collect = Collect::default();
+    let crawler = builder().item_pipeline(collect.clone()).build();
+
+    crawler
+        .crawl(NumbersSpider {
+            name: "numbers",
+            count: 10,
+        })
+        .await
+        .unwrap();
+    crawler
+        .crawl(NumbersSpider {
+            name: "more-numbers",
+            count: 5,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        collect.events(),
+        [
+            "open numbers",
+            "close after 10 items",
+            "open more-numbers",
+            "close after 15 items",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn pipeline_failing_to_open_closes_the_crawl() {
+    let collect = Collect::default();
+    let crawler = builder()
+        .item_pipeline(collect.clone())
+        .item_pipeline(Unavailable)
+        .build();
+
+    let report = crawler
+        .crawl(NumbersSpider {
+            name: "numbers",
+            count: 10,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(report.close_reason, CloseReason::PipelineFailed);
+    assert_eq!(report.pages_visited, 0);
+    assert_eq!(collect.events(), ["open numbers", "close after 0 items"]);
+}
+
+#[tokio::test]
+async fn pipeline_for_another_item_type_fails_the_crawl() {
+    let stats = StatsCollector::new();
+    let crawler = builder().stats(stats.clone()).item_pipeline(Titles).build();
+
+    let result = crawler
+        .crawl(NumbersSpider {
+            name: "numbers",
+            count: 10,
+        })
+        .await;
+
+    let err = result.unwrap_err();
+    assert!(
+        matches!(err, Error::PipelineItemType { item: "u32", .. }),
+        "{}",
+        err
+    );
+    assert_eq!(stats.counter("downloader/request_count"), 0);
+}
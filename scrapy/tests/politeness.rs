@@ -0,0 +1,208 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use scrapy::{
+    CrawlerBuilder, DownloadSlot, Downloader, Error, Request, Response, Spider, StatusCode,
+};
+
+/// A spider over `hosts` sites where each site's index page links to
+/// `pages` leaf pages.
+struct SitesSpider {
+    hosts: Vec<String>,
+    pages: usize,
+}
+
+/// A downloader where every download takes `latency`, recording when each
+/// download started and how many ran at once on every host.
+struct SitesDownloader {
+    latency: Duration,
+    record: Record,
+}
+
+#[derive(Clone, Default)]
+struct Record(Arc<Mutex<Activity>>);
+
+#[derive(Default)]
+struct Activity {
+    active: HashMap<String, usize>,
+    max_active: HashMap<String, usize>,
+    max_total: usize,
+    starts: HashMap<String, Vec<Instant>>,
+}
+
+/// Returns a spider over `hosts` and a downloader recording its downloads.
+fn sites(hosts: &[&str], pages: usize, latency: Duration) -> (SitesSpider, SitesDownloader) {
+    let spider = SitesSpider {
+        hosts: hosts.iter().map(|host| host.to_string()).collect(),
+        pages,
+    };
+    let downloader = SitesDownloader {
+        latency,
+        record: Record::default(),
+    };
+
+    (spider, downloader)
+}
+
+impl Record {
+    fn max_active(&self, host: &str) -> usize {
+        self.0.lock().unwrap().max_active[host]
+    }
+
+    fn max_total(&self) -> usize {
+        self.0.lock().unwrap().max_total
+    }
+
+    fn starts(&self, host: &str) -> Vec<Instant> {
+        let mut starts = self.0.lock().unwrap().starts[host].clone();
+        starts.sort();
+        starts
+    }
+
+    /// The shortest time between two downloads starting on `host`.
+    fn min_gap(&self, host: &str) -> Duration {
+        self.starts(host)
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .min()
+            .unwrap()
+    }
+}
+
+#[async_trait]
+impl Downloader for SitesDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let host = request.url.split('/').nth(2).unwrap().to_string();
+        {
+            let mut record = self.record.0.lock().unwrap();
+            let active = record.active.entry(host.clone()).or_default();
+            *active += 1;
+            let active = *active;
+            let max_active = record.max_active.entry(host.clone()).or_default();
+            *max_active = (*max_active).max(active);
+            let total = record.active.values().sum();
+            record.max_total = record.max_total.max(total);
+            record
+                .starts
+                .entry(host.clone())
+                .or_default()
+                .push(Instant::now());
+        }
+
+        tokio::time::sleep(self.latency).await;
+        *self.record.0.lock().unwrap().active.get_mut(&host).unwrap() -= 1;
+
+        Ok(Response {
+            url: request.url.clone(),
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body: Vec::new(),
+            elapsed: self.latency,
+            request: request.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Spider for SitesSpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("sites")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        self.hosts
+            .iter()
+            .map(|host| format!("http://{}/", host))
+            .collect()
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        let url = response.url.as_str();
+        let links = if url.ends_with('/') {
+            (0..self.pages)
+                .map(|page| format!("{}{}", url, page).into())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok((Vec::new(), links))
+    }
+}
+
+#[tokio::test]
+async fn slot_concurrency_caps_each_host() {
+    let (spider, downloader) = sites(
+        &["a.test", "b.test", "c.test"],
+        12,
+        Duration::from_millis(20),
+    );
+    let record = downloader.record.clone();
+    let crawler = CrawlerBuilder::new()
+        .downloader(downloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(16)
+        .slot_concurrency(2)
+        .build();
+
+    let report = crawler.crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 39);
+    for host in ["a.test", "b.test", "c.test"] {
+        assert_eq!(record.max_active(host), 2, "{}", host);
+    }
+    assert!(record.max_total() > 2);
+}
+
+#[tokio::test]
+async fn domain_slot_overrides_the_defaults() {
+    let (spider, downloader) = sites(&["slow.test", "fast.test"], 5, Duration::ZERO);
+    let record = downloader.record.clone();
+    let crawler = CrawlerBuilder::new()
+        .downloader(downloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(4)
+        .domain_slot(
+            "slow.test",
+            DownloadSlot::new()
+                .concurrency(1)
+                .delay(Duration::from_millis(50)),
+        )
+        .build();
+
+    crawler.crawl(spider).await.unwrap();
+
+    assert!(record.min_gap("slow.test") >= Duration::from_millis(45));
+    assert!(record.min_gap("fast.test") < Duration::from_millis(45));
+}
+
+#[tokio::test]
+async fn slow_host_does_not_hold_back_others() {
+    let (spider, downloader) = sites(&["slow.test", "fast.test"], 20, Duration::ZERO);
+    let record = downloader.record.clone();
+    let crawler = CrawlerBuilder::new()
+        .downloader(downloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(1)
+        .domain_slot(
+            "slow.test",
+            DownloadSlot::new().delay(Duration::from_millis(50)),
+        )
+        .build();
+
+    crawler.crawl(spider).await.unwrap();
+
+    // The slow site takes a second, but waiting on it must not keep the fast
+    // site's pages from being scraped right away.
+    let slow = record.starts("slow.test");
+    let fast = record.starts("fast.test");
+    assert!(slow[20] - slow[0] >= Duration::from_millis(950));
+    assert!(fast[20] - fast[0] < Duration::from_millis(200));
+}
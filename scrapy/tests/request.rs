@@ -0,0 +1,189 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{CrawlerBuilder, Method, Request, Response, Spider};
+
+use common::StubDownloader;
+
+mod common;
+
+/// A spider whose start request links to pages 1 to 3, tagging each link with
+/// its page number and the URL it was found on, and recording every request
+/// it scrapes.
+#[derive(Default)]
+struct MetaSpider {
+    scraped: Arc<Mutex<Vec<Request>>>,
+}
+
+#[async_trait]
+impl Spider for MetaSpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("meta")
+    }
+
+    fn start_requests(&self) -> Vec<Request> {
+        vec![Request::new("http://meta.test/").meta("page", 0)]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        self.scraped.lock().unwrap().push(response.request.clone());
+        if response.request.meta["page"] != 0 {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let links = (1..=3)
+            .map(|page| {
+                Request::new(format!("/{}", page))
+                    .meta("page", page)
+                    .meta("referer", response.url.as_str())
+            })
+            .collect();
+
+        Ok((Vec::new(), links))
+    }
+}
+
+/// A spider that requests the same URL with different methods and bodies.
+#[derive(Default)]
+struct FormSpider {
+    scraped: Arc<Mutex<Vec<Request>>>,
+}
+
+#[async_trait]
+impl Spider for FormSpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("form")
+    }
+
+    fn start_requests(&self) -> Vec<Request> {
+        let search = || Request::new("http://form.test/search");
+        vec![
+            search(),
+            search(),
+            search().method(Method::POST).body("q=rust"),
+            search().method(Method::POST).body("q=rust"),
+            search().method(Method::POST).body("q=go"),
+        ]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        self.scraped.lock().unwrap().push(response.request.clone());
+        Ok((Vec::new(), Vec::new()))
+    }
+}
+
+/// A spider whose start page links to itself with its query parameters in
+/// another order, and to a page with an unusual query.
+#[derive(Default)]
+struct QuerySpider {
+    fetched: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Spider for QuerySpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("query")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://query.test/?b=%20&a")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        self.fetched
+            .lock()
+            .unwrap()
+            .push(response.request.url.clone());
+        if response.request.url != "http://query.test/?b=%20&a" {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let links = vec![
+            Request::new("/?a&b=%20"),
+            Request::new("/search?q=a+b&flag"),
+        ];
+        Ok((Vec::new(), links))
+    }
+}
+
+fn builder() -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+}
+
+#[tokio::test]
+async fn meta_reaches_the_spider_with_the_request() {
+    let spider = MetaSpider::default();
+    let scraped = spider.scraped.clone();
+
+    let report = builder().build().crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 4);
+    let scraped = scraped.lock().unwrap();
+    for page in 1..=3 {
+        let request = scraped
+            .iter()
+            .find(|request| request.url == format!("http://meta.test/{}", page))
+            .unwrap();
+        assert_eq!(request.meta["page"], page);
+        assert_eq!(request.meta["referer"], "http://meta.test/");
+    }
+}
+
+#[tokio::test]
+async fn requests_differing_in_method_or_body_are_not_duplicates() {
+    let spider = FormSpider::default();
+    let scraped = spider.scraped.clone();
+
+    let report = builder().build().crawl(spider).await.unwrap();
+
+    assert_eq!(report.pages_visited, 3);
+    let mut scraped = scraped
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| (request.method.clone(), request.body.clone()))
+        .collect::<Vec<_>>();
+    scraped.sort_by(|a, b| (a.0.as_str(), &a.1).cmp(&(b.0.as_str(), &b.1)));
+    assert_eq!(
+        scraped,
+        [
+            (Method::GET, Vec::new()),
+            (Method::POST, b"q=go".to_vec()),
+            (Method::POST, b"q=rust".to_vec()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn requests_are_fetched_as_given() {
+    let spider = QuerySpider::default();
+    let fetched = spider.fetched.clone();
+
+    builder().build().crawl(spider).await.unwrap();
+
+    // The reordered link is a duplicate of the start URL, but neither query
+    // is rewritten when fetched.
+    let mut fetched = fetched.lock().unwrap().clone();
+    fetched.sort();
+    assert_eq!(
+        fetched,
+        [
+            "http://query.test/?b=%20&a",
+            "http://query.test/search?q=a+b&flag",
+        ]
+    );
+}
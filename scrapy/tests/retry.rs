@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use scrapy::{CrawlerBuilder, Request, Response, RetryPolicy, ScrapeFailure, Spider};
+
+use common::StubDownloader;
+
+mod common;
+
+/// A spider whose index page links to `pages` pages. `/flaky` fails twice
+/// before succeeding, `/broken` always fails, and `/fatal` always fails with
+/// an error that is not worth retrying.
+struct FlakySpider {
+    pages: usize,
+    attempts: Arc<Mutex<HashMap<String, Vec<Instant>>>>,
+}
+
+impl FlakySpider {
+    fn new(pages: usize) -> Self {
+        Self {
+            pages,
+            attempts: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Spider for FlakySpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("flaky")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://flaky.test/")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        let url = response.url.as_str();
+        let attempt = {
+            let mut attempts = self.attempts.lock().unwrap();
+            let attempts = attempts.entry(url.to_string()).or_default();
+            attempts.push(Instant::now());
+            attempts.len()
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        match url.rsplit('/').next().unwrap() {
+            "" => {
+                let mut links = vec!["/flaky".into(), "/broken".into(), "/fatal".into()];
+                links.extend((0..self.pages).map(|page| format!("/{}", page).into()));
+                Ok((Vec::new(), links))
+            }
+            "flaky" if attempt <= 2 => Err(String::from("timed out")),
+            "broken" => Err(String::from("timed out")),
+            "fatal" => Err(String::from("not found")),
+            _ => Ok((Vec::new(), Vec::new())),
+        }
+    }
+
+    fn is_retryable(&self, error: &String) -> bool {
+        error != "not found"
+    }
+}
+
+fn builder() -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(1)
+}
+
+#[tokio::test]
+async fn failed_scrapes_are_retried_until_they_give_up() {
+    let spider = FlakySpider::new(0);
+    let attempts = spider.attempts.clone();
+    let retry = RetryPolicy::new()
+        .max_attempts(3)
+        .backoff(Duration::from_millis(10))
+        .jitter(false);
+
+    let report = builder().retry(retry).build().crawl(spider).await.unwrap();
+
+    let attempts = attempts.lock().unwrap();
+    assert_eq!(attempts["http://flaky.test/flaky"].len(), 3);
+    assert_eq!(attempts["http://flaky.test/broken"].len(), 3);
+    assert_eq!(attempts["http://flaky.test/fatal"].len(), 1);
+    assert_eq!(report.retries, 4);
+    assert_eq!(report.scrape_errors, 6);
+    assert_eq!(report.pages_visited, 4);
+    assert_eq!(
+        report.failed_urls["http://flaky.test/broken"],
+        ScrapeFailure {
+            attempts: 3,
+            error: String::from("timed out"),
+        }
+    );
+    assert_eq!(report.failed_urls["http://flaky.test/fatal"].attempts, 1);
+    assert_eq!(report.failed_urls.len(), 2);
+}
+
+#[tokio::test]
+async fn backoff_grows_exponentially() {
+    let spider = FlakySpider::new(0);
+    let attempts = spider.attempts.clone();
+    let retry = RetryPolicy::new()
+        .max_attempts(4)
+        .backoff(Duration::from_millis(50))
+        .jitter(false);
+
+    builder().retry(retry).build().crawl(spider).await.unwrap();
+
+    let attempts = attempts.lock().unwrap();
+    let broken = &attempts["http://flaky.test/broken"];
+    assert!(broken[1] - broken[0] >= Duration::from_millis(50));
+    assert!(broken[2] - broken[1] >= Duration::from_millis(100));
+    assert!(broken[3] - broken[2] >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn waiting_retries_do_not_hold_a_concurrency_slot() {
+    let spider = FlakySpider::new(20);
+    let attempts = spider.attempts.clone();
+    let retry = RetryPolicy::new()
+        .max_attempts(2)
+        .backoff(Duration::from_millis(500))
+        .jitter(false);
+
+    builder().retry(retry).build().crawl(spider).await.unwrap();
+
+    // Every page is scraped while the broken one waits out its backoff.
+    let attempts = attempts.lock().unwrap();
+    let retried_at = attempts["http://flaky.test/broken"][1];
+    for page in 0..20 {
+        assert!(attempts[&format!("http://flaky.test/{}", page)][0] < retried_at);
+    }
+}
+
+#[tokio::test]
+async fn without_a_policy_failures_are_given_up_at_once() {
+    let report = builder().build().crawl(FlakySpider::new(0)).await.unwrap();
+
+    assert_eq!(report.retries, 0);
+    assert_eq!(report.failed_urls.len(), 3);
+    assert!(report
+        .failed_urls
+        .values()
+        .all(|failure| failure.attempts == 1));
+}
@@ -0,0 +1,216 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{CrawlerBuilder, Downloader, Error, Request, Response, RobotsTxt, Spider, StatusCode};
+
+/// A site serving `body` as `/robots.txt` with `status`, counting how often
+/// it is requested, and empty pages everywhere else.
+#[derive(Clone)]
+struct Site {
+    status: StatusCode,
+    body: &'static str,
+    hits: Arc<AtomicUsize>,
+}
+
+impl Site {
+    fn new(status: u16, body: &'static str) -> Self {
+        Self {
+            status: StatusCode::from_u16(status).unwrap(),
+            body,
+            hits: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+#[async_trait]
+impl Downloader for Site {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let (status, body) = if request.url.ends_with("/robots.txt") {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+            (self.status, self.body)
+        } else {
+            (StatusCode::OK, "")
+        };
+
+        Ok(Response {
+            url: request.url.clone(),
+            status,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        })
+    }
+}
+
+/// A spider whose index page links to `/public/{n}` and `/private/{n}`.
+struct SiteSpider {
+    pages: usize,
+}
+
+#[async_trait]
+impl Spider for SiteSpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("site")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://site.test/")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        let url = response.url.as_str();
+        if !url.ends_with('/') {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let links = (0..self.pages)
+            .flat_map(|page| [format!("/public/{}", page), format!("/private/{}", page)])
+            .map(Request::from)
+            .collect();
+
+        Ok((Vec::new(), links))
+    }
+}
+
+fn builder(site: &Site, user_agent: &str) -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(site.clone())
+        .delay(Duration::ZERO)
+        .obey_robots_txt(user_agent)
+}
+
+#[tokio::test]
+async fn disallowed_urls_are_not_crawled() {
+    let site = Site::new(200, "User-agent: *\nDisallow: /private\n");
+
+    let report = builder(&site, "mybot")
+        .build()
+        .crawl(SiteSpider { pages: 5 })
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 6);
+    assert_eq!(report.robots_blocked, 5);
+    assert_eq!(site.hits.load(Ordering::SeqCst), 1);
+    assert_eq!(report.stats.counter("downloader/request_count"), 7);
+}
+
+#[tokio::test]
+async fn rules_are_matched_on_the_user_agent_token() {
+    let site = Site::new(
+        200,
+        "User-agent: mybot\nDisallow: /\n\nUser-agent: *\nDisallow:\n",
+    );
+
+    let blocked = builder(&site, "MyBot/2.1 (+https://mybot.test)").build();
+    let allowed = builder(&site, "mybotanist/1.0").build();
+    let (blocked, allowed) = tokio::join!(
+        blocked.crawl(SiteSpider { pages: 5 }),
+        allowed.crawl(SiteSpider { pages: 5 }),
+    );
+    let (blocked, allowed) = (blocked.unwrap(), allowed.unwrap());
+
+    assert_eq!(blocked.pages_visited, 0);
+    assert_eq!(blocked.robots_blocked, 1);
+    assert_eq!(allowed.pages_visited, 11);
+    assert_eq!(allowed.robots_blocked, 0);
+}
+
+#[tokio::test]
+async fn crawl_delay_slows_down_the_site() {
+    let site = Site::new(200, "User-agent: *\nCrawl-delay: 0.05\n");
+
+    let report = builder(&site, "mybot")
+        .build()
+        .crawl(SiteSpider { pages: 3 })
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 7);
+    assert!(report.duration >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn missing_robots_txt_allows_everything() {
+    let site = Site::new(404, "");
+
+    let report = builder(&site, "mybot")
+        .build()
+        .crawl(SiteSpider { pages: 5 })
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 11);
+    assert_eq!(report.robots_blocked, 0);
+}
+
+#[tokio::test]
+async fn unreachable_robots_txt_disallows_everything() {
+    let site = Site::new(503, "");
+
+    let report = builder(&site, "mybot")
+        .build()
+        .crawl(SiteSpider { pages: 5 })
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 0);
+    assert_eq!(report.robots_blocked, 1);
+}
+
+#[test]
+fn wildcards_and_anchors_match() {
+    let robots = RobotsTxt::parse(
+        "User-agent: *\n\
+         Disallow: /*.pdf$\n\
+         Disallow: /search?*q=\n\
+         Allow: /search?lang=en$\n",
+    );
+
+    assert!(!robots.is_allowed("mybot", "/docs/manual.pdf"));
+    assert!(robots.is_allowed("mybot", "/docs/manual.pdf.html"));
+    assert!(!robots.is_allowed("mybot", "/search?lang=fr&q=rust"));
+    assert!(robots.is_allowed("mybot", "/search?lang=en"));
+}
+
+#[test]
+fn groups_sitemaps_and_crawl_delays_are_parsed() {
+    let robots = RobotsTxt::parse(
+        "# comment\n\
+         User-agent: slowbot\n\
+         User-agent: otherbot\n\
+         Crawl-delay: 2.5\n\
+         Disallow: /tmp # trailing comment\n\
+         \n\
+         Sitemap: https://example.com/sitemap.xml\n",
+    );
+
+    assert_eq!(
+        robots.crawl_delay("otherbot"),
+        Some(Duration::from_millis(2500))
+    );
+    assert_eq!(robots.crawl_delay("mybot"), None);
+    assert!(!robots.is_allowed("slowbot", "/tmp/file"));
+    assert!(robots.is_allowed("mybot", "/tmp/file"));
+    assert_eq!(robots.sitemaps(), ["https://example.com/sitemap.xml"]);
+}
+
+#[test]
+fn groups_match_the_whole_product_token() {
+    let robots = RobotsTxt::parse("User-agent: bot\nDisallow: /\n");
+
+    assert!(!robots.is_allowed("bot", "/page"));
+    assert!(!robots.is_allowed("BOT/1.0", "/page"));
+    assert!(robots.is_allowed("botanist/1.0", "/page"));
+    assert!(robots.is_allowed("mybot/1.0", "/page"));
+}
@@ -0,0 +1,122 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{CrawlerBuilder, PriorityScheduler, Request, Response, Spider};
+
+use common::StubDownloader;
+
+mod common;
+
+/// A spider over a binary tree where page `n` links to `2n + 1` and `2n + 2`,
+/// recording the order pages are scraped in. Links to pages listed in
+/// `urgent` are requested with priority 10.
+struct TreeSpider {
+    urgent: Vec<usize>,
+    scraped: Arc<Mutex<Vec<usize>>>,
+}
+
+impl TreeSpider {
+    fn new(urgent: Vec<usize>) -> Self {
+        Self {
+            urgent,
+            scraped: Arc::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Spider for TreeSpider {
+    type Item = ();
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("tree")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://tree.test/0")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<()>, Vec<Request>), String> {
+        let page: usize = response.url.rsplit('/').next().unwrap().parse().unwrap();
+        self.scraped.lock().unwrap().push(page);
+
+        let links = [2 * page + 1, 2 * page + 2]
+            .into_iter()
+            .map(|child| {
+                let priority = if self.urgent.contains(&child) { 10 } else { 0 };
+                Request::new(format!("http://tree.test/{}", child)).priority(priority)
+            })
+            .collect();
+
+        Ok((Vec::new(), links))
+    }
+}
+
+/// Scrapes one page at a time, so pages are scraped in scheduling order.
+fn builder() -> CrawlerBuilder {
+    CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(1)
+        .crawling_queue_capacity(1)
+        .max_depth(2)
+}
+
+#[tokio::test]
+async fn breadth_first_scrapes_level_by_level() {
+    let spider = TreeSpider::new(Vec::new());
+    let scraped = spider.scraped.clone();
+
+    builder()
+        .scheduler(PriorityScheduler::breadth_first())
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    assert_eq!(*scraped.lock().unwrap(), [0, 1, 2, 3, 4, 5, 6]);
+}
+
+#[tokio::test]
+async fn depth_first_follows_the_latest_link_first() {
+    let spider = TreeSpider::new(Vec::new());
+    let scraped = spider.scraped.clone();
+
+    builder()
+        .scheduler(PriorityScheduler::depth_first())
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    assert_eq!(*scraped.lock().unwrap(), [0, 2, 6, 5, 1, 4, 3]);
+}
+
+#[tokio::test]
+async fn higher_priorities_are_scraped_first() {
+    let spider = TreeSpider::new(vec![2, 6]);
+    let scraped = spider.scraped.clone();
+
+    builder()
+        .scheduler(PriorityScheduler::breadth_first())
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    assert_eq!(*scraped.lock().unwrap(), [0, 2, 6, 1, 5, 3, 4]);
+}
+
+#[tokio::test]
+async fn default_order_is_first_in_first_out() {
+    let spider = TreeSpider::new(Vec::new());
+    let scraped = spider.scraped.clone();
+
+    builder().build().crawl(spider).await.unwrap();
+
+    assert_eq!(*scraped.lock().unwrap(), [0, 1, 2, 3, 4, 5, 6]);
+}
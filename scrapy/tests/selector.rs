@@ -0,0 +1,136 @@
+use scrapy::{Document, SelectorError};
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Quotes</title></head>
+<body>
+  <div class="quote" data-id="1">
+    <span class="text">Less is &ldquo;more&rdquo; &amp; <b>better</b></span>
+    <small class="author">Ada</small>
+    <a href="/tag/life">life</a><a href="/tag/love">love</a>
+  </div>
+  <div class="quote" data-id="2">
+    <span class="text">Hi</span>
+    <small class="author">Bob</small>
+  </div>
+  <li class="next"><a href="/page/2/">Next</a></li>
+</body>
+</html>"#;
+
+#[test]
+fn css_selects_text_and_attributes() {
+    let document = Document::parse(PAGE);
+
+    let quotes = document.css("div.quote").unwrap();
+    assert_eq!(quotes.len(), 2);
+    assert_eq!(
+        quotes.css("span.text").unwrap().text_all(),
+        ["Less is \u{201c}more\u{201d} & better", "Hi"]
+    );
+    assert_eq!(quotes.attr_all("data-id"), ["1", "2"]);
+    assert_eq!(
+        document.css("li.next a").unwrap().attr("href").as_deref(),
+        Some("/page/2/")
+    );
+    assert_eq!(
+        document.css("small.author").unwrap().get().as_deref(),
+        Some(r#"<small class="author">Ada</small>"#)
+    );
+    assert!(document.css("table").unwrap().is_empty());
+    assert_eq!(document.css("table").unwrap().text(), None);
+}
+
+#[test]
+fn selections_are_queried_per_element() {
+    let document = Document::parse(PAGE);
+
+    let tags = document
+        .css(".quote")
+        .unwrap()
+        .iter()
+        .map(|quote| quote.css("a").unwrap().text_all())
+        .collect::<Vec<_>>();
+    assert_eq!(tags, [vec!["life", "love"], vec![]]);
+}
+
+#[test]
+fn xpath_selects_elements_attributes_and_values() {
+    let document = Document::parse(PAGE);
+
+    assert_eq!(
+        document.xpath("//a/@href").unwrap().get_all(),
+        ["/tag/life", "/tag/love", "/page/2/"]
+    );
+    assert_eq!(
+        document
+            .xpath("//div[@data-id='2']/small/text()")
+            .unwrap()
+            .get()
+            .as_deref(),
+        Some("Bob")
+    );
+    assert_eq!(
+        document.xpath("count(//div)").unwrap().get().as_deref(),
+        Some("2")
+    );
+
+    // Relative expressions are evaluated against each selected element.
+    let quotes = document.css(".quote").unwrap();
+    assert_eq!(quotes.xpath("small").unwrap().text_all(), ["Ada", "Bob"]);
+    assert_eq!(
+        quotes.xpath("../li/a").unwrap().css("a").unwrap().len(),
+        0,
+        "CSS only selects descendants"
+    );
+    assert_eq!(
+        quotes
+            .xpath("ancestor::body")
+            .unwrap()
+            .css("li a")
+            .unwrap()
+            .text()
+            .as_deref(),
+        Some("Next")
+    );
+
+    // Clones are queried on their own copy of the document.
+    let clone = document.clone();
+    drop(document);
+    assert_eq!(clone.xpath("//small").unwrap().text_all(), ["Ada", "Bob"]);
+}
+
+#[test]
+fn regular_expressions_extract_groups() {
+    let document = Document::parse(PAGE);
+    let links = document.xpath("//a/@href").unwrap();
+
+    assert_eq!(links.re(r"/tag/\w+").unwrap(), ["/tag/life", "/tag/love"]);
+    assert_eq!(links.re(r"/tag/(\w+)").unwrap(), ["life", "love"]);
+    assert_eq!(
+        links.re_first(r"/page/(\d+)/").unwrap().as_deref(),
+        Some("2")
+    );
+    assert_eq!(document.re(r"data-id=.(\d)").unwrap(), ["1", "2"]);
+}
+
+#[test]
+fn invalid_queries_are_errors() {
+    let document = Document::parse(PAGE);
+
+    assert!(matches!(
+        document.css("div[").unwrap_err(),
+        SelectorError::Css { query, .. } if query == "div["
+    ));
+    assert!(matches!(
+        document.xpath("//div[").unwrap_err(),
+        SelectorError::XPath { query, .. } if query == "//div["
+    ));
+    assert!(matches!(
+        document.xpath("unknown()").unwrap_err(),
+        SelectorError::XPath { .. }
+    ));
+    assert!(matches!(
+        document.css("a").unwrap().re("(").unwrap_err(),
+        SelectorError::Regex(_)
+    ));
+}
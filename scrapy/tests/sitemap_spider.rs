@@ -0,0 +1,204 @@
+use std::{io::Write, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use flate2::{write::GzEncoder, Compression};
+use scrapy::{
+    CrawlerBuilder, Downloader, Error, Request, Response, SelectorError, SitemapSpider, Spider,
+    StatusCode,
+};
+
+use common::Collect;
+
+mod common;
+
+const ROBOTS: &str = "User-agent: *\nDisallow:\nSitemap: http://shop.test/sitemap-index.xml\n";
+
+const INDEX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>http://shop.test/sitemap-products.xml.gz</loc></sitemap>
+  <sitemap><loc>http://shop.test/sitemap-nested.xml</loc><lastmod>2023-06-01</lastmod></sitemap>
+  <sitemap><loc>http://shop.test/sitemap-archive.xml</loc><lastmod>2019-01-01</lastmod></sitemap>
+  <sitemap><loc>http://shop.test/sitemap-broken.xml</loc></sitemap>
+</sitemapindex>"#;
+
+const PRODUCTS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>http://shop.test/product/1</loc><lastmod>2023-11-05T14:30:00+01:00</lastmod></url>
+  <url><loc>http://shop.test/product/2</loc><lastmod>2020-02-02</lastmod></url>
+  <url><loc> http://shop.test/product/3 </loc></url>
+  <url><loc>http://shop.test/category/books</loc><lastmod>2023-11-05T14:30Z</lastmod></url>
+  <url><loc>http://shop.test/about</loc></url>
+</urlset>"#;
+
+const NESTED: &str = r#"<sitemapindex>
+  <sitemap><loc>http://shop.test/sitemap-more.xml</loc></sitemap>
+</sitemapindex>"#;
+
+const MORE: &str = r#"<urlset><url><loc>http://shop.test/product/4</loc></url></urlset>"#;
+
+const ARCHIVE: &str = r#"<urlset><url><loc>http://shop.test/product/old</loc></url></urlset>"#;
+
+/// A downloader serving a shop's robots.txt and sitemaps, and empty pages.
+struct ShopDownloader;
+
+#[async_trait]
+impl Downloader for ShopDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let path = request.url.trim_start_matches("http://shop.test");
+        let body = match path {
+            "/robots.txt" => ROBOTS.as_bytes().to_vec(),
+            "/sitemap-index.xml" => INDEX.as_bytes().to_vec(),
+            "/sitemap-products.xml.gz" => {
+                let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+                gzip.write_all(PRODUCTS.as_bytes())?;
+                gzip.finish()?
+            }
+            "/sitemap-nested.xml" => NESTED.as_bytes().to_vec(),
+            "/sitemap-more.xml" => MORE.as_bytes().to_vec(),
+            "/sitemap-archive.xml" => ARCHIVE.as_bytes().to_vec(),
+            "/sitemap-broken.xml" => b"<urlset><url>".to_vec(),
+            "/sitemap-huge.xml.gz" => {
+                let mut gzip = GzEncoder::new(Vec::new(), Compression::fast());
+                gzip.write_all(MORE.trim_end_matches("</urlset>").as_bytes())?;
+                gzip.write_all(&vec![b' '; 50 * 1024 * 1024])?;
+                gzip.write_all(b"</urlset>")?;
+                gzip.finish()?
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(Response {
+            url: request.url.clone(),
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body,
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        })
+    }
+}
+
+/// Scrapes every page into its URL, prefixed with the callback it went to.
+struct ShopSpider;
+
+#[async_trait]
+impl Spider for ShopSpider {
+    type Item = String;
+    type Error = Error;
+
+    fn name(&self) -> String {
+        String::from("shop")
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<String>, Vec<Request>), Error> {
+        Ok((vec![format!("scrape {}", response.url)], Vec::new()))
+    }
+
+    async fn parse(
+        &self,
+        callback: &str,
+        response: &Response,
+    ) -> Option<Result<(Vec<String>, Vec<Request>), Error>> {
+        match callback {
+            "parse_product" | "parse_category" => Some(Ok((
+                vec![format!("{} {}", callback, response.url)],
+                Vec::new(),
+            ))),
+            _ => None,
+        }
+    }
+}
+
+async fn crawl(spider: SitemapSpider<ShopSpider>) -> Vec<String> {
+    let collect = Collect::default();
+    CrawlerBuilder::new()
+        .downloader(ShopDownloader)
+        .delay(Duration::ZERO)
+        .item_pipeline(collect.clone())
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    collect.sorted()
+}
+
+#[tokio::test]
+async fn sitemaps_are_discovered_from_robots_txt() {
+    let spider = SitemapSpider::new(ShopSpider).sitemap_url("http://shop.test/robots.txt");
+
+    assert_eq!(
+        crawl(spider).await,
+        [
+            "scrape http://shop.test/about",
+            "scrape http://shop.test/category/books",
+            "scrape http://shop.test/product/1",
+            "scrape http://shop.test/product/2",
+            "scrape http://shop.test/product/3",
+            "scrape http://shop.test/product/4",
+            "scrape http://shop.test/product/old",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn pages_are_routed_by_rules() {
+    let spider = SitemapSpider::new(ShopSpider)
+        .sitemap_url("http://shop.test/sitemap-index.xml")
+        .follow("products|nested|more")
+        .unwrap()
+        .rule("/product/", "parse_product")
+        .unwrap()
+        .rule("/category/", "parse_category")
+        .unwrap();
+
+    assert_eq!(
+        crawl(spider).await,
+        [
+            "parse_category http://shop.test/category/books",
+            "parse_product http://shop.test/product/1",
+            "parse_product http://shop.test/product/2",
+            "parse_product http://shop.test/product/3",
+            "parse_product http://shop.test/product/4",
+        ]
+    );
+}
+
+#[test]
+fn invalid_patterns_are_errors() {
+    assert!(matches!(
+        SitemapSpider::new(ShopSpider).follow("("),
+        Err(SelectorError::Regex(_))
+    ));
+    assert!(matches!(
+        SitemapSpider::new(ShopSpider).rule("[a-", "parse_product"),
+        Err(SelectorError::Regex(_))
+    ));
+}
+
+#[tokio::test]
+async fn old_entries_are_skipped() {
+    let spider = SitemapSpider::new(ShopSpider)
+        .sitemap_url("http://shop.test/sitemap-index.xml")
+        .modified_since(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap());
+
+    // Entries without a lastmod are kept.
+    assert_eq!(
+        crawl(spider).await,
+        [
+            "scrape http://shop.test/about",
+            "scrape http://shop.test/category/books",
+            "scrape http://shop.test/product/1",
+            "scrape http://shop.test/product/3",
+            "scrape http://shop.test/product/4",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn sitemaps_too_large_once_decompressed_are_skipped() {
+    let spider = SitemapSpider::new(ShopSpider).sitemap_url("http://shop.test/sitemap-huge.xml.gz");
+
+    assert!(crawl(spider).await.is_empty());
+}
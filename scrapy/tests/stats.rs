@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use scrapy::{
+    CrawlerBuilder, Downloader, Error, ItemError, ItemPipeline, Request, Response, Spider,
+    StatsCollector, StatusCode,
+};
+
+/// A site whose home page links to two pages, one of which is missing, and
+/// to a page on a host that cannot be reached.
+struct Site;
+
+#[async_trait]
+impl Downloader for Site {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let (status, body) = match request.url.as_str() {
+            "http://site.test/" => (StatusCode::OK, "home"),
+            "http://site.test/about" => (StatusCode::OK, "about"),
+            "http://site.test/gone" => (StatusCode::NOT_FOUND, "gone"),
+            _ => return Err(Error::Download("unreachable".into())),
+        };
+
+        Ok(Response {
+            url: request.url.clone(),
+            status,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            elapsed: Duration::ZERO,
+            request: request.clone(),
+        })
+    }
+}
+
+/// Scrapes the body of each page as an item, counting missing pages in the
+/// crawler's stats, and fails on the about page.
+struct SiteSpider {
+    stats: StatsCollector,
+}
+
+#[async_trait]
+impl Spider for SiteSpider {
+    type Item = String;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("site")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://site.test/")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<String>, Vec<Request>), String> {
+        match response.url.as_str() {
+            "http://site.test/" => {
+                let links = ["/about", "/gone", "http://down.test/"]
+                    .into_iter()
+                    .map(Request::new)
+                    .collect();
+                Ok((vec![response.text().into_owned()], links))
+            }
+            "http://site.test/about" => Err(String::from("no about")),
+            _ => {
+                self.stats.inc("site/missing_pages");
+                Ok((vec![response.text().into_owned()], Vec::new()))
+            }
+        }
+    }
+}
+
+/// Drops the items of missing pages, keeping track of the longest item.
+struct DropMissing {
+    stats: StatsCollector,
+}
+
+#[async_trait]
+impl ItemPipeline<String> for DropMissing {
+    async fn process_item(&self, item: String) -> Result<String, ItemError> {
+        self.stats.set_max("site/longest_item", item.len() as i64);
+        if item == "gone" {
+            return Err(ItemError::Dropped(item));
+        }
+        Ok(item)
+    }
+}
+
+#[tokio::test]
+async fn crawls_record_their_stats() {
+    // Stats recorded before the crawl are kept.
+    let stats = StatsCollector::new();
+    stats.inc("left/over");
+
+    let crawler = CrawlerBuilder::new()
+        .downloader(Site)
+        .delay(Duration::ZERO)
+        .stats(stats.clone())
+        .item_pipeline(DropMissing {
+            stats: stats.clone(),
+        })
+        .build();
+    let report = crawler
+        .crawl(SiteSpider {
+            stats: stats.clone(),
+        })
+        .await
+        .unwrap();
+
+    let counters = report
+        .stats
+        .counters
+        .iter()
+        .map(|(key, value)| (key.as_str(), *value))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        counters,
+        [
+            ("downloader/request_count", 4),
+            ("downloader/response_bytes", 13),
+            ("downloader/response_count", 3),
+            ("downloader/response_status_count/200", 2),
+            ("downloader/response_status_count/404", 1),
+            ("error_count/download/download", 1),
+            ("error_count/scrape", 1),
+            ("item_dropped_count", 1),
+            ("item_processed_count", 1),
+            ("item_scraped_count", 2),
+            ("left/over", 1),
+            ("request_depth_count/0", 1),
+            ("request_depth_count/1", 3),
+            ("site/missing_pages", 1),
+        ]
+    );
+    assert_eq!(report.stats.gauge("request_depth_max"), Some(1));
+    assert_eq!(report.stats.gauge("site/longest_item"), Some(4));
+
+    // The crawler's collector is the one the spider and pipeline were given.
+    assert_eq!(stats.snapshot(), report.stats);
+
+    // Crawls sharing a collector add their stats up.
+    crawler
+        .crawl(SiteSpider {
+            stats: stats.clone(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(stats.counter("downloader/request_count"), 8);
+    assert_eq!(stats.counter("left/over"), 1);
+}
+
+#[test]
+fn gauges_keep_the_value_they_are_set_to() {
+    let stats = StatsCollector::new();
+
+    stats.set("queue/size", 3);
+    stats.set_max("depth/max", 2);
+    stats.set_max("depth/max", 1);
+    stats.set_min("latency/min", 20);
+    stats.set_min("latency/min", 10);
+    assert_eq!(stats.gauge("queue/size"), Some(3));
+    assert_eq!(stats.gauge("depth/max"), Some(2));
+    assert_eq!(stats.gauge("latency/min"), Some(10));
+    assert_eq!(stats.gauge("queue/length"), None);
+
+    assert_eq!(stats.inc("pages"), 1);
+    assert_eq!(stats.inc_by("pages", 2), 3);
+    assert_eq!(stats.counter("items"), 0);
+
+    assert_eq!(
+        stats.snapshot().to_string(),
+        "depth/max: 2\nlatency/min: 10\npages: 3\nqueue/size: 3\n"
+    );
+
+    stats.clear();
+    assert_eq!(stats.snapshot(), Default::default());
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use scrapy::{
+    CloseReason, CrawlReport, CrawlerBuilder, ItemError, ItemPipeline, Request, Response, Spider,
+};
+
+use common::StubDownloader;
+
+mod common;
+
+/// A spider over a synthetic link graph where page `n` links to a handful of
+/// other pages derived from `n`, producing cycles and duplicate links.
+struct GraphSpider {
+    pages: usize,
+    fanout: usize,
+    starts: Vec<usize>,
+}
+
+impl GraphSpider {
+    fn new(seed: usize) -> Self {
+        let pages = 1 + seed % 40;
+        let fanout = seed % 4;
+        let starts = (0..1 + seed % 3).map(|i| (seed + i * 5) % pages).collect();
+
+        Self {
+            pages,
+            fanout,
+            starts,
+        }
+    }
+
+    fn links(&self, page: usize) -> Vec<usize> {
+        (0..self.fanout)
+            .map(|k| (page * 31 + k * 7 + 1) % self.pages)
+            .collect()
+    }
+
+    fn fails(page: usize) -> bool {
+        page % 13 == 5
+    }
+
+    /// The report a correct crawl of this graph must produce.
+    fn expected(&self) -> (usize, usize) {
+        let mut seen: HashSet<usize> = self.starts.iter().copied().collect();
+        let mut queue: VecDeque<usize> = seen.iter().copied().collect();
+        let mut failures = 0;
+
+        while let Some(page) = queue.pop_front() {
+            if Self::fails(page) {
+                failures += 1;
+                continue;
+            }
+
+            for link in self.links(page) {
+                if seen.insert(link) {
+                    queue.push_back(link);
+                }
+            }
+        }
+
+        (seen.len(), failures)
+    }
+}
+
+#[async_trait]
+impl Spider for GraphSpider {
+    type Item = usize;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("graph")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        self.starts
+            .iter()
+            .map(|page| format!("http://pages.test/{}", page))
+            .collect()
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<usize>, Vec<Request>), String> {
+        let url = response.url.as_str();
+        let page: usize = url.rsplit('/').next().unwrap().parse().unwrap();
+
+        for _ in 0..page % 3 {
+            tokio::task::yield_now().await;
+        }
+
+        if Self::fails(page) {
+            return Err(format!("failed: {}", url));
+        }
+
+        let urls = self
+            .links(page)
+            .into_iter()
+            .map(|link| format!("http://pages.test/{}", link).into())
+            .collect();
+
+        Ok((vec![page], urls))
+    }
+}
+
+/// A pipeline stage yielding to the runtime before passing each item on.
+struct YieldingPipeline;
+
+#[async_trait]
+impl ItemPipeline<usize> for YieldingPipeline {
+    async fn process_item(&self, item: usize) -> Result<usize, ItemError> {
+        tokio::task::yield_now().await;
+        Ok(item)
+    }
+}
+
+async fn crawl(seed: usize) -> CrawlReport {
+    let crawler = CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .crawling_concurrency(1 + seed % 4)
+        .processing_concurrency(1 + seed % 3)
+        .crawling_queue_capacity(1 + seed % 2)
+        .processing_queue_capacity(1 + seed % 2)
+        .item_pipeline(YieldingPipeline)
+        .build();
+
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        crawler.crawl(GraphSpider::new(seed)),
+    )
+    .await
+    .unwrap_or_else(|_| panic!("crawl {} did not terminate", seed))
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn crawls_visit_every_reachable_page_exactly_once() {
+    for seed in 0..3000 {
+        let (pages, failures) = GraphSpider::new(seed).expected();
+        let report = crawl(seed).await;
+
+        assert_eq!(report.pages_visited, pages, "seed {}", seed);
+        assert_eq!(report.scrape_errors, failures, "seed {}", seed);
+        assert_eq!(report.items_scraped, pages - failures, "seed {}", seed);
+        assert_eq!(report.items_processed, pages - failures, "seed {}", seed);
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn crawls_terminate_on_a_single_thread() {
+    for seed in 0..1000 {
+        let (pages, _) = GraphSpider::new(seed).expected();
+        assert_eq!(crawl(seed).await.pages_visited, pages, "seed {}", seed);
+    }
+}
+
+#[tokio::test]
+async fn crawl_without_start_urls_terminates() {
+    let spider = GraphSpider {
+        pages: 1,
+        fanout: 0,
+        starts: vec![],
+    };
+
+    let report = CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .build()
+        .crawl(spider)
+        .await
+        .unwrap();
+
+    assert_eq!(report.pages_visited, 0);
+}
+
+/// A spider where every page links to the next one, so the crawl never ends
+/// on its own.
+/// A spider over an endless chain of pages, counting the pages it scraped.
+#[derive(Clone, Default)]
+struct EndlessSpider {
+    scraped: Arc<AtomicUsize>,
+}
+
+impl EndlessSpider {
+    fn scraped(&self) -> usize {
+        self.scraped.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl Spider for EndlessSpider {
+    type Item = usize;
+    type Error = String;
+
+    fn name(&self) -> String {
+        String::from("endless")
+    }
+
+    fn start_urls(&self) -> Vec<String> {
+        vec![String::from("http://pages.test/0")]
+    }
+
+    async fn scrape(&self, response: &Response) -> Result<(Vec<usize>, Vec<Request>), String> {
+        let url = response.url.as_str();
+        let page: usize = url.rsplit('/').next().unwrap().parse().unwrap();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        self.scraped.fetch_add(1, Ordering::SeqCst);
+        Ok((
+            vec![page],
+            vec![format!("http://pages.test/{}", page + 1).into()],
+        ))
+    }
+}
+
+#[tokio::test]
+async fn stopped_crawl_drains_and_returns() {
+    let crawler = CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .build();
+    let handle = crawler.handle();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle.stop();
+    });
+
+    let report = tokio::time::timeout(
+        Duration::from_secs(10),
+        crawler.crawl(EndlessSpider::default()),
+    )
+    .await
+    .expect("stopped crawl did not terminate")
+    .unwrap();
+
+    assert_eq!(report.close_reason, CloseReason::Cancelled);
+    assert!(report.pages_visited > 0);
+    assert_eq!(report.items_processed, report.items_scraped);
+}
+
+#[tokio::test]
+async fn paused_crawl_resumes_and_accounts_for_paused_time() {
+    let crawler = CrawlerBuilder::new()
+        .downloader(StubDownloader)
+        .delay(Duration::ZERO)
+        .max_pages(200)
+        .build();
+    let handle = crawler.handle();
+    let stats = crawler.stats();
+    let spider = EndlessSpider::default();
+    let crawl = tokio::spawn({
+        let spider = spider.clone();
+        async move { crawler.crawl(spider).await }
+    });
+
+    wait_until(|| spider.scraped() > 0).await;
+    handle.pause();
+
+    // Pages already fetched are scraped, but no new page is fetched once
+    // they all were.
+    let fetched = stats.counter("downloader/request_count") as usize;
+    wait_until(|| spider.scraped() == fetched).await;
+    assert_eq!(
+        stats.counter("downloader/request_count") as usize,
+        fetched,
+        "pages were fetched while paused"
+    );
+
+    handle.resume();
+    let report = tokio::time::timeout(Duration::from_secs(10), crawl)
+        .await
+        .expect("resumed crawl did not finish")
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(report.close_reason, CloseReason::MaxPages);
+    assert_eq!(report.pages_visited, 200);
+    assert!(report.paused > Duration::ZERO);
+    assert!(report.active_duration() < report.duration);
+}
+
+/// Waits until `condition` holds, failing the test if it takes too long.
+async fn wait_until<F>(condition: F)
+where
+    F: Fn() -> bool,
+{
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+    .await
+    .expect("condition never held");
+}
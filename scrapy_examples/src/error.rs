@@ -5,6 +5,12 @@ pub enum AppError {
     #[error("Reqwest Error: {0}")]
     Reqwest(#[from] reqwest::Error),
 
+    #[error("Scrapy Error: {0}")]
+    Scrapy(#[from] scrapy::Error),
+
+    #[error("Selector Error: {0}")]
+    Selector(#[from] scrapy::SelectorError),
+
     #[error("WebDriver Error: {0}")]
     WebDriver(#[from] WebDriverError),
 
@@ -13,4 +19,7 @@ pub enum AppError {
 
     #[error("Spider is not valid: {0}")]
     InvalidSpider(String),
+
+    #[error("Cannot infer the feed format of {0}, use a .jsonl, .json, .csv or .xml file")]
+    InvalidFeed(String),
 }
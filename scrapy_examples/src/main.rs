@@ -1,10 +1,16 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
 use clap::{Parser, Subcommand};
 use error::AppError;
 use log::LevelFilter;
-use scrapy::CrawlerBuilder;
-use spiders::{BooksSpider, HackerNewsSpider, QuotesSpider, WebReviewsSpider};
+use scrapy::{
+    CrawlReport, CrawlerBuilder, FeedExporter, FeedFormat, HttpCache, Spider, StatsCollector,
+};
+use serde::Serialize;
+use spiders::{
+    BooksSpider, HackerNewsSpider, LogReviews, PrintBooks, PrintQuotes, PrintStories, QuotesSpider,
+    ValidateQuotes, WebReviewsSpider,
+};
 
 mod error;
 mod spiders;
@@ -19,6 +25,23 @@ pub enum Command {
         /// The spider to run
         #[arg(short, long)]
         spider: String,
+
+        /// A directory to persist the crawl state in, so an interrupted crawl can be resumed
+        #[arg(short, long)]
+        job_dir: Option<PathBuf>,
+
+        /// A file to export the scraped items to, in a format inferred from its extension,
+        /// gzipped if it ends with `.gz`. `{spider}` and `{time}` are replaced in the path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// A directory to cache downloaded pages in, so they are downloaded only once
+        #[arg(long)]
+        http_cache: Option<PathBuf>,
+
+        /// Only serve pages from the HTTP cache, failing on the pages missing from it
+        #[arg(long, requires = "http_cache")]
+        offline: bool,
     },
 }
 
@@ -46,40 +69,106 @@ async fn main() -> Result<(), AppError> {
                     println!("{}", name);
                 }
             }
-            Command::Run { spider } => {
+            Command::Run {
+                spider,
+                job_dir,
+                output,
+                http_cache,
+                offline,
+            } => {
                 let spider_name = spider.as_str();
-                let crawler = CrawlerBuilder::new()
+                let feed = output
+                    .map(|path| match FeedFormat::from_path(&path) {
+                        Some(format) => Ok((path, format)),
+                        None => Err(AppError::InvalidFeed(path.display().to_string())),
+                    })
+                    .transpose()?;
+                let stats = StatsCollector::new();
+                let mut builder = CrawlerBuilder::new()
+                    .stats(stats.clone())
                     .delay(Duration::from_millis(200))
                     .crawling_concurrency(2)
-                    .processing_concurrency(500)
-                    .build();
+                    .processing_concurrency(500);
+
+                if let Some(job_dir) = job_dir {
+                    builder = builder.job_dir(job_dir);
+                }
+
+                if let Some(http_cache) = http_cache {
+                    let cache = HttpCache::new(http_cache).offline(offline);
+                    builder = builder.downloader_middleware(cache);
+                }
 
-                match spider_name {
+                let report = match spider_name {
                     "quotes" => {
                         let spider = QuotesSpider::new();
-                        crawler.crawl(spider).await;
+                        let builder = builder
+                            .item_pipeline(ValidateQuotes)
+                            .item_pipeline(PrintQuotes);
+                        run(builder, spider, feed).await?
                     }
                     "books" => {
                         let headless = true;
                         let spider = BooksSpider::new(headless).await?;
-                        crawler.crawl(spider.clone()).await;
-                        spider.close().await?
+                        let builder = builder
+                            .downloader(spider.downloader())
+                            .item_pipeline(PrintBooks);
+                        let report = run(builder, spider.crawl_spider(), feed).await;
+                        spider.close().await?;
+                        report?
                     }
                     "hacker-news" => {
                         let spider = HackerNewsSpider::new();
-                        crawler.crawl(spider).await;
+                        let builder = builder.item_pipeline(PrintStories::new(stats));
+                        run(builder, spider, feed).await?
                     }
                     "web-reviews" => {
                         let headless = false;
                         let spider = WebReviewsSpider::new(headless).await?;
-                        crawler.crawl(spider.clone()).await;
-                        // spider.close().await?
+                        let builder = builder
+                            .downloader(spider.downloader())
+                            .item_pipeline(LogReviews::new(stats));
+                        let report = run(builder, spider.clone(), feed).await;
+                        spider.close().await?;
+                        report?
                     }
                     _ => return Err(AppError::InvalidSpider(spider_name.to_string())),
                 };
+
+                log::info!("crawl finished: {:#?}", report);
             }
         }
     }
 
     Ok(())
 }
+
+/// Crawls `spider`, exporting its items to `feed` if given and stopping the
+/// crawl gracefully on Ctrl-C.
+async fn run<S>(
+    mut builder: CrawlerBuilder,
+    spider: S,
+    feed: Option<(PathBuf, FeedFormat)>,
+) -> Result<CrawlReport, scrapy::Error>
+where
+    S: Spider + 'static,
+    S::Item: Serialize + Send + 'static,
+    S::Error: std::fmt::Display + Send + 'static,
+{
+    if let Some((path, format)) = feed {
+        let gzip = path.extension().is_some_and(|extension| extension == "gz");
+        builder = builder.item_pipeline(FeedExporter::<S::Item>::new(path, format).gzip(gzip));
+    }
+
+    let crawler = builder.build();
+
+    let handle = crawler.handle();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            log::warn!("received Ctrl-C, stopping the crawl");
+            handle.stop();
+        }
+    });
+
+    crawler.crawl(spider).await
+}
@@ -1,35 +1,28 @@
-use scraper::{Html, Selector};
-use scrapy::FromHTML;
+use scrapy::{Document, FromHTML};
+use serde::Serialize;
 
 use crate::error::AppError;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct BookItem {
     pub title: Option<String>,
+    pub price: Option<String>,
 }
 
 impl FromHTML for BookItem {
     type Error = AppError;
-    type Output = Vec<Self>;
+    type Output = Self;
 
+    /// Parses a book's product page.
     fn from_html(html: &str) -> Result<Self::Output, Self::Error>
     where
         Self: Sized,
     {
-        let document = Html::parse_document(html);
-        let book_selector = Selector::parse(".product_pod").unwrap();
-        let title_selector = Selector::parse("h3 a").unwrap();
+        let document = Document::parse(html);
 
-        let mut books = Vec::new();
-
-        for book in document.select(&book_selector) {
-            let title: Option<_> = book
-                .select(&title_selector)
-                .next()
-                .and_then(|e| e.inner_html().parse().ok());
-            books.push(Self { title })
-        }
-
-        Ok(books)
+        Ok(Self {
+            title: document.css(".product_main h1")?.text(),
+            price: document.css(".product_main .price_color")?.text(),
+        })
     }
 }
@@ -1,5 +1,8 @@
 mod item;
 pub use item::BookItem;
 
+mod pipelines;
+pub use pipelines::PrintBooks;
+
 mod spider;
 pub use spider::BooksSpider;
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use scrapy::{ItemError, ItemPipeline};
+
+use super::item::BookItem;
+
+pub struct PrintBooks;
+
+#[async_trait]
+impl ItemPipeline<BookItem> for PrintBooks {
+    async fn process_item(&self, item: BookItem) -> Result<BookItem, ItemError> {
+        if let Some(title) = &item.title {
+            println!("Book Title: {:?}", title);
+        }
+
+        Ok(item)
+    }
+}
@@ -1,12 +1,12 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scrapy::{FromHTML, Spider};
+use scrapy::{CrawlSpider, FromHTML, LinkExtractor, Request, Response, Rule, Spider};
 use serde_json::json;
 use thirtyfour::{DesiredCapabilities, WebDriver};
 use tokio::sync::Mutex;
 
-use crate::error::AppError;
+use crate::{error::AppError, spiders::WebDriverDownloader};
 
 use super::item::BookItem;
 
@@ -29,26 +29,24 @@ impl Spider for BooksSpider {
         vec![self.base_url.to_string()]
     }
 
-    async fn scrape(&self, url: &str) -> Result<(Vec<Self::Item>, Vec<String>), AppError> {
-        log::info!("visiting: {}", url);
-
-        let html = {
-            let webdriver = self.driver.lock().await;
-            webdriver.goto(&url).await?;
-            webdriver.source().await?
-        };
-
-        let next_pages_link = vec![];
-
-        Ok((Self::Item::from_html(&html)?, next_pages_link))
+    /// Scrapes the home page, which only has links to follow.
+    async fn scrape(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<Self::Item>, Vec<Request>), AppError> {
+        log::info!("visiting: {}", response.url);
+        Ok((Vec::new(), Vec::new()))
     }
 
-    async fn process(&self, item: Self::Item) -> Result<(), AppError> {
-        if let Some(title) = item.title {
-            println!("Book Title: {:?}", title);
+    async fn parse(
+        &self,
+        callback: &str,
+        response: &Response,
+    ) -> Option<Result<(Vec<Self::Item>, Vec<Request>), AppError>> {
+        match callback {
+            "parse_product" => Some(self.parse_product(response)),
+            _ => None,
         }
-
-        Ok(())
     }
 }
 
@@ -78,20 +76,36 @@ impl BooksSpider {
         })
     }
 
+    /// Returns a spider following the catalogue's pages and scraping every
+    /// book on them.
+    pub fn crawl_spider(&self) -> CrawlSpider<Self> {
+        let pages = LinkExtractor::new().restrict_css("li.next");
+        let books = LinkExtractor::new().restrict_css(".product_pod h3");
+
+        CrawlSpider::new(self.clone())
+            .rule(Rule::new(pages))
+            .rule(Rule::new(books).callback("parse_product"))
+    }
+
+    /// Returns a downloader rendering pages in this spider's browser.
+    pub fn downloader(&self) -> WebDriverDownloader {
+        WebDriverDownloader::new(self.driver.clone())
+    }
+
     pub async fn close(&self) -> Result<(), AppError> {
         let driver = self.driver.lock().await;
         driver.clone().quit().await?;
         Ok(())
     }
 
-    #[allow(unused)]
-    fn normalize_url(&self, url: &str) -> String {
-        let url = url.trim();
-
-        if url.starts_with('/') {
-            return format!("{}{}", self.base_url, url);
-        }
+    /// Scrapes a book's product page.
+    fn parse_product(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<BookItem>, Vec<Request>), AppError> {
+        log::info!("visiting product: {}", response.url);
 
-        url.to_string()
+        let book = BookItem::from_html(&response.text())?;
+        Ok((vec![book], Vec::new()))
     }
 }
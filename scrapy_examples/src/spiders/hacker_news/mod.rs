@@ -1,9 +1,6 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
-
 use async_trait::async_trait;
-use futures::StreamExt;
 
-use scrapy::Spider;
+use scrapy::{ItemError, ItemPipeline, Request, Response, Spider, StatsCollector};
 use serde::{Deserialize, Serialize};
 
 use crate::error::AppError;
@@ -18,15 +15,11 @@ pub struct HackerNewsStory {
     time: Option<i64>,
 }
 
-pub struct HackerNewsSpider {
-    item_index: AtomicUsize,
-}
+pub struct HackerNewsSpider;
 
 impl HackerNewsSpider {
     pub fn new() -> Self {
-        Self {
-            item_index: AtomicUsize::new(0),
-        }
+        Self
     }
 }
 
@@ -44,39 +37,47 @@ impl Spider for HackerNewsSpider {
         vec!["https://hacker-news.firebaseio.com/v0/topstories.json".to_string()]
     }
 
-    async fn scrape(&self, url: &str) -> Result<(Vec<Self::Item>, Vec<String>), Self::Error> {
-        log::info!("visiting: {}", url);
+    async fn scrape(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<Self::Item>, Vec<Request>), Self::Error> {
+        log::info!("visiting: {}", response.url);
 
-        // Make a GET request to Hacker News API
-        let top_story_ids: Vec<i32> = reqwest::get(url).await?.json().await?;
+        if response.url.ends_with("/topstories.json") {
+            let top_story_ids: Vec<i32> = response.json()?;
 
-        // Take the top 10 story IDs
-        let top_10_story_ids = top_story_ids.iter().take(10).cloned();
+            // Follow the top 10 stories
+            let story_links = top_story_ids
+                .iter()
+                .take(10)
+                .map(|story_id| format!("item/{}.json", story_id).into())
+                .collect();
+
+            return Ok((Vec::new(), story_links));
+        }
 
-        let data: Vec<Result<_, Self::Error>> = futures::stream::iter(top_10_story_ids)
-            .map(|story_id| async move {
-                let story_url = format!(
-                    "https://hacker-news.firebaseio.com/v0/item/{}.json",
-                    story_id
-                );
-                let story_data: HackerNewsStory = reqwest::get(&story_url).await?.json().await?;
-                Ok(story_data)
-            })
-            .buffer_unordered(10)
-            .collect::<Vec<_>>()
-            .await;
+        let story: HackerNewsStory = response.json()?;
 
-        let top_stories = data.into_iter().flatten().collect::<Vec<_>>();
+        Ok((vec![story], Vec::new()))
+    }
+}
 
-        let next_pages_link = vec![];
+pub struct PrintStories {
+    stats: StatsCollector,
+}
 
-        Ok((top_stories, next_pages_link))
+impl PrintStories {
+    pub fn new(stats: StatsCollector) -> Self {
+        Self { stats }
     }
+}
 
-    async fn process(&self, story: Self::Item) -> Result<(), Self::Error> {
-        let i = self.item_index.load(Ordering::SeqCst);
+#[async_trait]
+impl ItemPipeline<HackerNewsStory> for PrintStories {
+    async fn process_item(&self, story: HackerNewsStory) -> Result<HackerNewsStory, ItemError> {
+        let i = self.stats.inc("hacker_news/stories_printed");
 
-        println!("{}. {} (ID: {})", i + 1, story.title, story.id);
+        println!("{}. {} (ID: {})", i, story.title, story.id);
 
         if let Some(url) = &story.url {
             println!("   URL: {}", url);
@@ -97,7 +98,6 @@ impl Spider for HackerNewsSpider {
             }
         }
 
-        self.item_index.fetch_add(1, Ordering::SeqCst);
-        Ok(())
+        Ok(story)
     }
 }
@@ -1,11 +1,14 @@
 mod books_spider;
-pub use books_spider::BooksSpider;
+pub use books_spider::{BooksSpider, PrintBooks};
 
 mod hacker_news;
-pub use hacker_news::HackerNewsSpider;
+pub use hacker_news::{HackerNewsSpider, PrintStories};
 
 mod quote_spider;
-pub use quote_spider::QuotesSpider;
+pub use quote_spider::{PrintQuotes, QuotesSpider, ValidateQuotes};
 
 mod web_reviews;
-pub use web_reviews::WebReviewsSpider;
+pub use web_reviews::{LogReviews, WebReviewsSpider};
+
+mod webdriver;
+pub use webdriver::WebDriverDownloader;
@@ -1,9 +1,9 @@
-use scraper::{Html, Selector};
-use scrapy::FromHTML;
+use scrapy::{Document, FromHTML};
+use serde::Serialize;
 
 use crate::error::AppError;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct QuotesItem {
     pub text: Option<String>,
     pub author: Option<String>,
@@ -31,25 +31,15 @@ impl FromHTML for QuotesItem {
     where
         Self: Sized,
     {
-        let document = Html::parse_document(html);
-        let quote_selector = Selector::parse(".quote").unwrap();
-        let author_selector = Selector::parse("small.author").unwrap();
-        let text_selector = Selector::parse("span.text").unwrap();
+        let document = Document::parse(html);
 
         let mut quotes = Vec::new();
 
-        for quote in document.select(&quote_selector) {
-            let text: Option<_> = quote
-                .select(&text_selector)
-                .next()
-                .and_then(|e| e.inner_html().parse().ok());
-
-            let author: Option<_> = quote
-                .select(&author_selector)
-                .next()
-                .and_then(|e| e.inner_html().parse().ok());
-
-            quotes.push(QuotesItem { text, author })
+        for quote in document.css(".quote")?.iter() {
+            quotes.push(QuotesItem {
+                text: quote.css("span.text")?.text(),
+                author: quote.css("small.author")?.text(),
+            })
         }
 
         Ok(quotes)
@@ -1,5 +1,8 @@
 mod item;
 pub use item::QuotesItem;
 
+mod pipelines;
+pub use pipelines::{PrintQuotes, ValidateQuotes};
+
 mod spider;
 pub use spider::QuotesSpider;
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use scrapy::{ItemError, ItemPipeline};
+
+use super::item::QuotesItem;
+
+/// Drops quotes missing their text or author.
+pub struct ValidateQuotes;
+
+#[async_trait]
+impl ItemPipeline<QuotesItem> for ValidateQuotes {
+    async fn process_item(&self, item: QuotesItem) -> Result<QuotesItem, ItemError> {
+        item.validate()
+            .map_err(|err| ItemError::Dropped(err.to_string()))?;
+        Ok(item)
+    }
+}
+
+pub struct PrintQuotes;
+
+#[async_trait]
+impl ItemPipeline<QuotesItem> for PrintQuotes {
+    async fn process_item(&self, item: QuotesItem) -> Result<QuotesItem, ItemError> {
+        println!("processing: {:#?}", item);
+        Ok(item)
+    }
+}
@@ -1,16 +1,13 @@
-use std::time::Duration;
-
 use async_trait::async_trait;
-use reqwest::Client;
 use scrapy::FromHTML;
-use scrapy::Spider;
+use scrapy::{LinkExtractor, Request, Response, Spider};
 
 use crate::error::AppError;
 
 use super::item::QuotesItem;
 
 pub struct QuotesSpider {
-    http_client: Client,
+    next_pages: LinkExtractor,
 }
 
 #[async_trait]
@@ -29,28 +26,22 @@ impl Spider for QuotesSpider {
         ]
     }
 
-    async fn scrape(&self, url: &str) -> Result<(Vec<Self::Item>, Vec<String>), AppError> {
-        log::info!("visiting: {}", url);
-        let http_res = self.http_client.get(url).send().await?.text().await?;
-        let next_pages_link = vec![];
-        Ok((Self::Item::from_html(&http_res)?, next_pages_link))
-    }
-
-    async fn process(&self, item: Self::Item) -> Result<(), AppError> {
-        println!("processing: {:#?}", item);
-        item.validate()?;
-        Ok(())
+    async fn scrape(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<Self::Item>, Vec<Request>), AppError> {
+        log::info!("visiting: {}", response.url);
+        let next_pages_link = self.next_pages.extract(response)?;
+        Ok((Self::Item::from_html(&response.text())?, next_pages_link))
     }
 }
 
 impl QuotesSpider {
     pub fn new() -> Self {
-        let http_timeout = Duration::from_secs(6);
-        let http_client = Client::builder()
-            .timeout(http_timeout)
-            .build()
-            .expect("spiders/quotes: Building HTTP client");
-
-        Self { http_client }
+        Self {
+            next_pages: LinkExtractor::new()
+                .restrict_css("li.next")
+                .allow_domains(["quotes.toscrape.com"]),
+        }
     }
 }
@@ -1,2 +1,5 @@
+mod pipelines;
+pub use pipelines::LogReviews;
+
 mod spider;
 pub use spider::WebReviewsSpider;
@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use scrapy::{ItemError, ItemPipeline, StatsCollector};
+
+pub struct LogReviews {
+    stats: StatsCollector,
+}
+
+impl LogReviews {
+    pub fn new(stats: StatsCollector) -> Self {
+        Self { stats }
+    }
+}
+
+#[async_trait]
+impl ItemPipeline<HashMap<String, String>> for LogReviews {
+    async fn process_item(
+        &self,
+        item: HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, ItemError> {
+        let i = self.stats.inc("web_reviews/reviews_logged");
+        log::info!("Processing: {}. {:?}", i, item);
+        Ok(item)
+    }
+}
@@ -1,23 +1,16 @@
-use std::{
-    collections::HashMap,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
-};
+use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
-use scrapy::Spider;
+use scrapy::{Request, Response, Spider};
 use serde_json::json;
 use thirtyfour::{ChromeCapabilities, WebDriver};
-use tokio::sync::RwLock;
+use tokio::sync::Mutex;
 
-use crate::error::AppError;
+use crate::{error::AppError, spiders::WebDriverDownloader};
 
 #[derive(Clone)]
 pub struct WebReviewsSpider {
-    driver: Arc<RwLock<WebDriver>>,
-    item_index: Arc<AtomicUsize>,
+    driver: Arc<Mutex<WebDriver>>,
 }
 
 #[async_trait]
@@ -33,25 +26,18 @@ impl Spider for WebReviewsSpider {
         vec!["https://www.sephora.nz/products/the-ordinary-niacinamide-10-percent-plus-zinc-1-percent/v/30ml".to_string()]
     }
 
-    async fn scrape(&self, url: &str) -> Result<(Vec<Self::Item>, Vec<String>), AppError> {
-        log::info!("Visiting: {}", url);
+    async fn scrape(
+        &self,
+        response: &Response,
+    ) -> Result<(Vec<Self::Item>, Vec<Request>), AppError> {
+        log::info!("Visiting: {}", response.url);
 
-        let webdriver = self.driver.read().await;
-
-        webdriver.goto(&url).await?;
-        let html = webdriver.source().await?;
+        let html = response.text();
 
         println!("next_button: {}", html);
 
         Ok((vec![], vec![]))
     }
-
-    async fn process(&self, item: Self::Item) -> Result<(), AppError> {
-        let i = self.item_index.load(Ordering::SeqCst);
-        log::info!("Processing: {}. {:?}", i + 1, item);
-        self.item_index.fetch_add(1, Ordering::SeqCst);
-        Ok(())
-    }
 }
 
 impl WebReviewsSpider {
@@ -82,13 +68,17 @@ impl WebReviewsSpider {
         let driver = WebDriver::new("http://localhost:9515", caps).await?;
 
         Ok(Self {
-            driver: Arc::new(RwLock::new(driver)),
-            item_index: AtomicUsize::new(0).into(),
+            driver: Arc::new(Mutex::new(driver)),
         })
     }
 
+    /// Returns a downloader rendering pages in this spider's browser.
+    pub fn downloader(&self) -> WebDriverDownloader {
+        WebDriverDownloader::new(self.driver.clone())
+    }
+
     pub async fn close(&self) -> Result<(), AppError> {
-        let driver = self.driver.read().await;
+        let driver = self.driver.lock().await;
         driver.clone().quit().await?;
         Ok(())
     }
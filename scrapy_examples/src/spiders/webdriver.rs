@@ -0,0 +1,51 @@
+use std::{sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use scrapy::{Downloader, Error, Request, Response, StatusCode};
+use thirtyfour::WebDriver;
+use tokio::sync::Mutex;
+
+/// A downloader rendering pages in a browser, for sites that build their
+/// content with JavaScript.
+///
+/// The browser only loads pages with `GET` and does not report the status
+/// code or headers it got, so every rendered page is reported as a `200 OK`
+/// without headers.
+#[derive(Clone)]
+pub struct WebDriverDownloader {
+    driver: Arc<Mutex<WebDriver>>,
+}
+
+impl WebDriverDownloader {
+    pub fn new(driver: Arc<Mutex<WebDriver>>) -> Self {
+        Self { driver }
+    }
+}
+
+#[async_trait]
+impl Downloader for WebDriverDownloader {
+    async fn fetch(&self, request: &Request) -> Result<Response, Error> {
+        let started_at = Instant::now();
+
+        // One page is rendered at a time, as the browser has a single window.
+        let driver = self.driver.lock().await;
+        let download = async {
+            driver.goto(&request.url).await?;
+            let html = driver.source().await?;
+            let url = driver.current_url().await?;
+            Ok((url.to_string(), html))
+        };
+        let (url, html) = download
+            .await
+            .map_err(|err: thirtyfour::prelude::WebDriverError| Error::Download(Box::new(err)))?;
+
+        Ok(Response {
+            url,
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body: html.into_bytes(),
+            elapsed: started_at.elapsed(),
+            request: request.clone(),
+        })
+    }
+}